@@ -21,4 +21,62 @@ pub struct Payload {
     pub active: bool,
     pub role: String,
     pub limit: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "id": 42,
+        "method": "GET",
+        "endpoint": "/api/users",
+        "headers": {
+            "accept": "application/json",
+            "authorization": "Bearer token"
+        },
+        "payload": {
+            "active": true,
+            "role": "admin",
+            "limit": 10
+        }
+    }"#;
+
+    fn sample() -> Request {
+        Request {
+            id: 42,
+            method: "GET".into(),
+            endpoint: "/api/users".into(),
+            headers: Headers {
+                accept: "application/json".into(),
+                authorization: "Bearer token".into(),
+            },
+            payload: Payload {
+                active: true,
+                role: "admin".into(),
+                limit: 10,
+            },
+        }
+    }
+
+    #[test]
+    fn sample_deserializes_into_request() {
+        let request: Request = serde_json::from_str(SAMPLE).unwrap();
+        assert_eq!(request, sample());
+    }
+
+    #[test]
+    fn request_roundtrips_through_json() {
+        let request: Request = serde_json::from_str(SAMPLE).unwrap();
+        let json = serde_json::to_string(&request).unwrap();
+        let again: Request = serde_json::from_str(&json).unwrap();
+        assert_eq!(again, request);
+    }
+
+    #[test]
+    fn missing_nested_field_fails() {
+        let json = SAMPLE.replace(r#""authorization": "Bearer token""#, r#""x": "y""#);
+        let err = serde_json::from_str::<Request>(&json).unwrap_err();
+        assert!(err.to_string().contains("missing field `authorization`"));
+    }
 }
\ No newline at end of file
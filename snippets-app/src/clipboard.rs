@@ -0,0 +1,72 @@
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+pub fn copy(text: &str) -> io::Result<()> {
+    #[cfg(target_os = "macos")]
+    let candidates: &[&[&str]] = &[&["pbcopy"]];
+    #[cfg(target_os = "windows")]
+    let candidates: &[&[&str]] = &[&["clip"]];
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let candidates: &[&[&str]] = &[
+        &["wl-copy"],
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+    ];
+
+    let mut last_err = io::Error::other("no clipboard tool available");
+    for candidate in candidates {
+        let mut command = Command::new(candidate[0]);
+        command.args(&candidate[1..]);
+        match pipe_to(&mut command, text) {
+            Ok(()) => return Ok(()),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
+
+fn pipe_to(command: &mut Command, text: &str) -> io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin was piped")
+        .write_all(text.as_bytes())?;
+
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{:?} exited with {status}",
+            command.get_program()
+        )))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_tool_is_an_error() {
+        let mut command = Command::new("snippets-app-no-such-clipboard");
+        assert!(pipe_to(&mut command, "echo hi").is_err());
+    }
+
+    #[test]
+    fn failing_tool_is_an_error() {
+        assert!(pipe_to(&mut Command::new("false"), "echo hi").is_err());
+    }
+
+    #[test]
+    fn successful_tool_is_ok() {
+        assert!(pipe_to(&mut Command::new("cat"), "echo hi").is_ok());
+    }
+}
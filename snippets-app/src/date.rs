@@ -0,0 +1,262 @@
+use std::{error::Error, fmt};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidDate(pub String);
+
+impl fmt::Display for InvalidDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid date '{}': expected YYYY-MM-DD or an RFC 3339 time such as 2024-03-01T09:30:00Z",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidDate {}
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidDuration(pub String);
+
+impl fmt::Display for InvalidDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid duration '{}': expected numbers with units s, m, h or d, such as 30d or 1h30m",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidDuration {}
+
+/// Formats unix seconds as an RFC 3339 timestamp in UTC.
+pub fn format(secs: u64) -> String {
+    let (year, month, day) = civil_from_days(secs / SECONDS_PER_DAY);
+    let time = secs % SECONDS_PER_DAY;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Parses `YYYY-MM-DD` (midnight UTC) or an RFC 3339 timestamp into unix
+/// seconds. Times before 1970 are rejected.
+pub fn parse(text: &str) -> Result<u64, InvalidDate> {
+    let invalid = || InvalidDate(text.to_string());
+    let (date, time) = match text.find(['T', 't']) {
+        Some(at) => (&text[..at], Some(&text[at + 1..])),
+        None => (text, None),
+    };
+
+    let [year, month, day] = fields(date, '-', [4, 2, 2]).ok_or_else(invalid)?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+    let mut secs = days_from_civil(year, month, day)
+        .checked_mul(SECONDS_PER_DAY as i64)
+        .ok_or_else(invalid)?;
+
+    if let Some(time) = time {
+        let offset_at = time.find(['Z', 'z', '+', '-']).ok_or_else(invalid)?;
+        let (clock, offset) = time.split_at(offset_at);
+        // Fractions of a second don't matter at the store's resolution.
+        let clock = match clock.split_once('.') {
+            Some((whole, fraction))
+                if !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                whole
+            }
+            Some(_) => return Err(invalid()),
+            None => clock,
+        };
+        let [hour, minute, second] = fields(clock, ':', [2, 2, 2]).ok_or_else(invalid)?;
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(invalid());
+        }
+        secs += (hour * 3600 + minute * 60 + second) as i64;
+
+        let east = match offset.as_bytes()[0] {
+            b'Z' | b'z' if offset.len() == 1 => 0,
+            sign @ (b'+' | b'-') => {
+                let [hours, minutes] = fields(&offset[1..], ':', [2, 2]).ok_or_else(invalid)?;
+                if hours > 23 || minutes > 59 {
+                    return Err(invalid());
+                }
+                let east = (hours * 3600 + minutes * 60) as i64;
+                if sign == b'+' {
+                    east
+                } else {
+                    -east
+                }
+            }
+            _ => return Err(invalid()),
+        };
+        secs -= east;
+    }
+    u64::try_from(secs).map_err(|_| invalid())
+}
+
+/// Parses a duration such as `90s`, `30d` or `1d12h` into seconds.
+pub fn parse_duration(text: &str) -> Result<u64, InvalidDuration> {
+    let invalid = || InvalidDuration(text.to_string());
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    let mut rest = text;
+    let mut total: u64 = 0;
+    while !rest.is_empty() {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let unit = match rest[digits..].chars().next() {
+            Some('s') => 1,
+            Some('m') => 60,
+            Some('h') => 60 * 60,
+            Some('d') => SECONDS_PER_DAY,
+            _ => return Err(invalid()),
+        };
+        let amount: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        total = amount
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + 1..];
+    }
+    Ok(total)
+}
+
+// Splits `text` on `separator` into fields of exactly the given digit counts.
+fn fields<const N: usize>(text: &str, separator: char, widths: [usize; N]) -> Option<[u64; N]> {
+    let mut parts = text.split(separator);
+    let mut values = [0; N];
+    for (value, width) in values.iter_mut().zip(widths) {
+        let part = parts.next()?;
+        if part.len() != width || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        *value = part.parse().ok()?;
+    }
+    parts.next().is_none().then_some(values)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Howard Hinnant's days_from_civil: days since 1970-01-01, negative before.
+fn days_from_civil(year: u64, month: u64, day: u64) -> i64 {
+    let year = year as i64 - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Howard Hinnant's civil_from_days, restricted to dates after 1970.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_the_epoch() {
+        assert_eq!(format(0), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn formats_dates_across_leap_days() {
+        assert_eq!(format(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format(1_709_251_199), "2024-02-29T23:59:59Z");
+        assert_eq!(format(1_709_251_200), "2024-03-01T00:00:00Z");
+    }
+
+    #[test]
+    fn parses_plain_dates_as_midnight_utc() {
+        assert_eq!(parse("1970-01-01"), Ok(0));
+        assert_eq!(parse("2024-02-29"), Ok(1_709_164_800));
+        assert_eq!(parse("2024-03-01"), Ok(1_709_251_200));
+    }
+
+    #[test]
+    fn parses_rfc3339_with_offsets_and_fractions() {
+        assert_eq!(parse("2024-02-29T23:59:59Z"), Ok(1_709_251_199));
+        assert_eq!(parse("2024-03-01T02:00:00+02:00"), Ok(1_709_251_200));
+        assert_eq!(parse("2024-02-29t19:00:00.250-05:00"), Ok(1_709_251_200));
+        for secs in [0, 951_782_400, 1_709_251_199] {
+            assert_eq!(parse(&format(secs)), Ok(secs));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_and_impossible_dates() {
+        for text in [
+            "",
+            "yesterday",
+            "2024-3-1",
+            "2024-02-30",
+            "2023-02-29",
+            "2024-13-01",
+            "2024-03-01T24:00:00Z",
+            "2024-03-01T10:00:00",
+            "2024-03-01T10:00Z",
+            "2024-03-01T10:00:00.Z",
+            "2024-03-01T10:00:00+0200",
+            "1969-12-31",
+        ] {
+            assert_eq!(parse(text), Err(InvalidDate(text.to_string())), "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_durations_with_mixed_units() {
+        assert_eq!(parse_duration("45s"), Ok(45));
+        assert_eq!(parse_duration("90m"), Ok(5400));
+        assert_eq!(parse_duration("30d"), Ok(30 * 86_400));
+        assert_eq!(parse_duration("1d12h30m"), Ok(131_400));
+        assert_eq!(parse_duration("0s"), Ok(0));
+    }
+
+    #[test]
+    fn rejects_malformed_durations() {
+        for text in [
+            "",
+            "30",
+            "d",
+            "1w",
+            "1.5h",
+            "-1d",
+            "1d 2h",
+            "99999999999999999999d",
+        ] {
+            assert_eq!(
+                parse_duration(text),
+                Err(InvalidDuration(text.to_string())),
+                "{text}"
+            );
+        }
+    }
+}
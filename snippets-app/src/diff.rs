@@ -0,0 +1,258 @@
+use std::fmt::Write;
+
+const CONTEXT: usize = 3;
+
+#[derive(Debug, PartialEq)]
+pub enum Change<'a> {
+    Same(usize, usize, &'a str),
+    Removed(usize, &'a str),
+    Added(usize, &'a str),
+}
+
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<Change<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut changes = Vec::with_capacity(old.len().max(new.len()));
+    for (i, line) in old[..prefix].iter().enumerate() {
+        changes.push(Change::Same(i + 1, i + 1, line));
+    }
+    align(
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+        prefix,
+        prefix,
+        &mut changes,
+    );
+    for k in (0..suffix).rev() {
+        let (i, j) = (old.len() - k, new.len() - k);
+        changes.push(Change::Same(i, j, old[i - 1]));
+    }
+    changes
+}
+
+// Hirschberg's algorithm: split the old side in half and find where the
+// new side splits, so only two rows of the LCS table are alive at a time.
+fn align<'a>(
+    old: &[&'a str],
+    new: &[&'a str],
+    old_start: usize,
+    new_start: usize,
+    changes: &mut Vec<Change<'a>>,
+) {
+    if old.is_empty() || new.is_empty() {
+        for (i, line) in old.iter().enumerate() {
+            changes.push(Change::Removed(old_start + i + 1, line));
+        }
+        for (j, line) in new.iter().enumerate() {
+            changes.push(Change::Added(new_start + j + 1, line));
+        }
+        return;
+    }
+    if old.len() == 1 {
+        let Some(at) = new.iter().position(|line| *line == old[0]) else {
+            changes.push(Change::Removed(old_start + 1, old[0]));
+            for (j, line) in new.iter().enumerate() {
+                changes.push(Change::Added(new_start + j + 1, line));
+            }
+            return;
+        };
+        for (j, line) in new[..at].iter().enumerate() {
+            changes.push(Change::Added(new_start + j + 1, line));
+        }
+        changes.push(Change::Same(old_start + 1, new_start + at + 1, old[0]));
+        for (j, line) in new[at + 1..].iter().enumerate() {
+            changes.push(Change::Added(new_start + at + j + 2, line));
+        }
+        return;
+    }
+
+    let mid = old.len() / 2;
+    let front = lcs_lengths(old[..mid].iter().copied(), new.iter().copied());
+    let back = lcs_lengths(old[mid..].iter().rev().copied(), new.iter().rev().copied());
+    let split = (0..=new.len())
+        .max_by_key(|&j| (front[j] + back[new.len() - j], std::cmp::Reverse(j)))
+        .unwrap_or(0);
+
+    align(&old[..mid], &new[..split], old_start, new_start, changes);
+    align(
+        &old[mid..],
+        &new[split..],
+        old_start + mid,
+        new_start + split,
+        changes,
+    );
+}
+
+// Last row of the LCS table for `old` against every prefix of `new`.
+fn lcs_lengths<'a>(
+    old: impl Iterator<Item = &'a str>,
+    new: impl Iterator<Item = &'a str> + Clone,
+) -> Vec<usize> {
+    let width = new.clone().count() + 1;
+    let mut row = vec![0; width];
+    let mut previous = vec![0; width];
+    for a in old {
+        std::mem::swap(&mut row, &mut previous);
+        for (j, b) in new.clone().enumerate() {
+            row[j + 1] = if a == b {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(row[j])
+            };
+        }
+    }
+    row
+}
+
+pub fn unified(old_label: &str, old: &str, new_label: &str, new: &str) -> String {
+    let changes = diff_lines(old, new);
+    let mut out = String::new();
+    if changes
+        .iter()
+        .all(|change| matches!(change, Change::Same(..)))
+    {
+        return out;
+    }
+
+    let _ = writeln!(out, "--- {old_label}\n+++ {new_label}");
+    let mut start = 0;
+    while let Some(first) = changes[start..]
+        .iter()
+        .position(|change| !matches!(change, Change::Same(..)))
+    {
+        let first = start + first;
+        let mut end = first;
+        // Extend the hunk while the next change is close enough to share context.
+        while let Some(next) = changes[end + 1..]
+            .iter()
+            .position(|change| !matches!(change, Change::Same(..)))
+        {
+            if next > 2 * CONTEXT {
+                break;
+            }
+            end += next + 1;
+        }
+        let hunk_start = first.saturating_sub(CONTEXT).max(start);
+        let hunk_end = (end + CONTEXT + 1).min(changes.len());
+        write_hunk(&mut out, &changes[hunk_start..hunk_end]);
+        start = hunk_end;
+    }
+    out
+}
+
+fn write_hunk(out: &mut String, hunk: &[Change]) {
+    let (mut old_start, mut new_start) = (None, None);
+    let (mut old_count, mut new_count) = (0, 0);
+    let mut body = String::new();
+    for change in hunk {
+        let _ = match change {
+            Change::Same(a, b, line) => {
+                old_start.get_or_insert(*a);
+                new_start.get_or_insert(*b);
+                old_count += 1;
+                new_count += 1;
+                writeln!(body, " {line}")
+            }
+            Change::Removed(a, line) => {
+                old_start.get_or_insert(*a);
+                old_count += 1;
+                writeln!(body, "-{line}")
+            }
+            Change::Added(b, line) => {
+                new_start.get_or_insert(*b);
+                new_count += 1;
+                writeln!(body, "+{line}")
+            }
+        };
+    }
+    let old_range = range(old_start, old_count);
+    let new_range = range(new_start, new_count);
+    let _ = writeln!(out, "@@ -{old_range} +{new_range} @@");
+    out.push_str(&body);
+}
+
+// Hunks always carry context, so a side only lacks lines when that text is
+// empty; `diff -u` reports it as line 0.
+fn range(start: Option<usize>, count: usize) -> String {
+    let start = start.unwrap_or(0);
+    match count {
+        1 => start.to_string(),
+        _ => format!("{start},{count}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_text_has_no_changes() {
+        let changes = diff_lines("a\nb", "a\nb");
+        assert_eq!(changes, [Change::Same(1, 1, "a"), Change::Same(2, 2, "b")]);
+        assert_eq!(unified("old", "a\nb", "new", "a\nb"), "");
+    }
+
+    #[test]
+    fn replaced_line_is_removed_then_added() {
+        let changes = diff_lines("a\nb\nc", "a\nx\nc");
+        assert_eq!(
+            changes,
+            [
+                Change::Same(1, 1, "a"),
+                Change::Removed(2, "b"),
+                Change::Added(2, "x"),
+                Change::Same(3, 3, "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_the_longest_common_subsequence() {
+        let changes = diff_lines("a\nb\nc\nd\ne", "x\nb\ny\nd\nz");
+        let same: Vec<_> = changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::Same(a, b, line) => Some((*a, *b, *line)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(same, [(2, 2, "b"), (4, 4, "d")]);
+        assert_eq!(changes.len(), 8);
+    }
+
+    #[test]
+    fn unified_output_has_hunk_headers() {
+        let out = unified("old", "a\nb", "new", "a\nc");
+        assert_eq!(out, "--- old\n+++ new\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n");
+    }
+
+    #[test]
+    fn distant_changes_get_separate_hunks() {
+        let old: String = (1..=20).map(|n| format!("{n}\n")).collect();
+        let new = old
+            .replacen("2\n", "two\n", 1)
+            .replace("19\n", "nineteen\n");
+        let out = unified("old", &old, "new", &new);
+        assert_eq!(
+            out,
+            "--- old\n+++ new\n\
+             @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+             @@ -16,5 +16,5 @@\n 16\n 17\n 18\n-19\n+nineteen\n 20\n"
+        );
+    }
+
+    #[test]
+    fn empty_side_is_reported_at_zero() {
+        let out = unified("old", "", "new", "a");
+        assert_eq!(out, "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n");
+    }
+}
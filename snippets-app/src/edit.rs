@@ -0,0 +1,173 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub fn editor() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// File extension for a snippet's language, so editors and viewers pick
+/// the right highlighting. Unknown or missing languages get `txt`.
+pub fn extension_for(language: Option<&str>) -> &'static str {
+    let Some(language) = language else {
+        return "txt";
+    };
+    match language.to_ascii_lowercase().as_str() {
+        "rust" | "rs" => "rs",
+        "python" | "py" => "py",
+        "sh" | "bash" | "shell" | "zsh" => "sh",
+        "sql" => "sql",
+        "javascript" | "js" => "js",
+        "typescript" | "ts" => "ts",
+        "go" | "golang" => "go",
+        "ruby" | "rb" => "rb",
+        "c" => "c",
+        "cpp" | "c++" => "cpp",
+        "java" => "java",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "html" => "html",
+        "css" => "css",
+        "markdown" | "md" => "md",
+        _ => "txt",
+    }
+}
+
+// Writes `contents` to a fresh file in the temp directory that only the
+// current user can read. The pid and clock suffix keep names that sanitize
+// the same apart, and create_new refuses to follow a planted symlink.
+pub fn temp_file(prefix: &str, name: &str, extension: &str, contents: &str) -> io::Result<PathBuf> {
+    let stem = file_stem(name);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut attempt = 0;
+    loop {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.subsec_nanos());
+        let path = env::temp_dir().join(format!(
+            "{prefix}{stem}-{}-{nanos:09}.{extension}",
+            std::process::id()
+        ));
+        match options.open(&path) {
+            Ok(mut file) => {
+                if let Err(err) = file.write_all(contents.as_bytes()) {
+                    fs::remove_file(&path).ok();
+                    return Err(err);
+                }
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 16 => {
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// Returns None when the editor left the text as it was.
+pub fn edit_text(
+    editor: &str,
+    name: &str,
+    extension: &str,
+    initial: &str,
+) -> io::Result<Option<String>> {
+    let file = temp_file("snippets-app-edit-", name, extension, initial)?;
+
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "editor command is empty"))?;
+    let status = Command::new(program).args(parts).arg(&file).status();
+    let edited = fs::read_to_string(&file);
+    fs::remove_file(&file).ok();
+
+    let status = status?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "editor exited with {status}; nothing was saved"
+        )));
+    }
+    let edited = edited?;
+    Ok((edited != initial).then_some(edited))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn file_stem_replaces_unsafe_characters() {
+        assert_eq!(file_stem("../etc/passwd"), "___etc_passwd");
+        assert_eq!(file_stem("Cool Rust pattern"), "Cool_Rust_pattern");
+    }
+
+    #[test]
+    fn temp_files_are_private_and_never_reused() {
+        let first = temp_file("snippets-app-test-", "a/b", "txt", "one").unwrap();
+        let second = temp_file("snippets-app-test-", "a_b", "txt", "two").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "one");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "two");
+        let mode = fs::metadata(&first).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn temp_files_carry_the_language_extension() {
+        assert_eq!(extension_for(Some("Rust")), "rs");
+        assert_eq!(extension_for(Some("python")), "py");
+        assert_eq!(extension_for(Some("bash")), "sh");
+        assert_eq!(extension_for(Some("cobol")), "txt");
+        assert_eq!(extension_for(None), "txt");
+
+        let file = temp_file("snippets-app-test-", "main", "rs", "fn main() {}").unwrap();
+        assert_eq!(file.extension().unwrap(), "rs");
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn unchanged_content_is_not_returned() {
+        assert_eq!(edit_text("true", "same", "sh", "echo hi").unwrap(), None);
+    }
+
+    #[test]
+    fn failing_editor_is_an_error() {
+        assert!(edit_text("false", "fails", "sh", "echo hi").is_err());
+    }
+
+    #[test]
+    fn edited_content_is_returned() {
+        let script = env::temp_dir().join(format!("snippets-app-editor-{}.sh", std::process::id()));
+        fs::write(&script, "#!/bin/sh\nprintf 'echo bye' > \"$1\"\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let edited = edit_text(script.to_str().unwrap(), "changed", "sh", "echo hi").unwrap();
+        assert_eq!(edited.as_deref(), Some("echo bye"));
+        fs::remove_file(script).unwrap();
+    }
+}
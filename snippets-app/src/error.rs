@@ -0,0 +1,48 @@
+use std::{error::Error, fmt, io};
+
+#[derive(Debug)]
+pub enum SnippetError {
+    NotFound(String),
+    AlreadyExists(String),
+    ChecksumMismatch(String),
+    NoSuchVersion(String, usize),
+    Binary(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for SnippetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnippetError::NotFound(name) => write!(f, "snippet '{name}' not found"),
+            SnippetError::AlreadyExists(name) => write!(f, "snippet '{name}' already exists"),
+            SnippetError::ChecksumMismatch(name) => {
+                write!(f, "snippet '{name}' does not match its checksum")
+            }
+            SnippetError::NoSuchVersion(name, version) => {
+                write!(f, "snippet '{name}' has no version {version}")
+            }
+            SnippetError::Binary(name) => {
+                write!(
+                    f,
+                    "snippet '{name}' holds binary data and can't be used as text"
+                )
+            }
+            SnippetError::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for SnippetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnippetError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnippetError {
+    fn from(err: io::Error) -> Self {
+        SnippetError::Io(err)
+    }
+}
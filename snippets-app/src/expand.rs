@@ -0,0 +1,111 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug, PartialEq)]
+pub struct UnsetVariables(pub Vec<String>);
+
+impl fmt::Display for UnsetVariables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unset environment variables: {}", self.0.join(", "))
+    }
+}
+
+impl Error for UnsetVariables {}
+
+pub fn expand_env(
+    code: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+    strict: bool,
+) -> Result<String, UnsetVariables> {
+    let mut out = String::with_capacity(code.len());
+    let mut unset: Vec<String> = Vec::new();
+    let mut rest = code;
+
+    while let Some(dollar) = rest.find('$') {
+        out.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+
+        let Some((name, consumed)) = variable_name(after) else {
+            out.push('$');
+            rest = after;
+            continue;
+        };
+        match lookup(name) {
+            Some(value) => out.push_str(&value),
+            None => {
+                out.push_str(&rest[dollar..dollar + 1 + consumed]);
+                if !unset.iter().any(|u| u == name) {
+                    unset.push(name.to_string());
+                }
+            }
+        }
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+
+    if unset.is_empty() || !strict {
+        Ok(out)
+    } else {
+        Err(UnsetVariables(unset))
+    }
+}
+
+fn variable_name(text: &str) -> Option<(&str, usize)> {
+    if let Some(braced) = text.strip_prefix('{') {
+        let end = braced.find('}')?;
+        let name = &braced[..end];
+        return is_name(name).then_some((name, end + 2));
+    }
+
+    let end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    let name = &text[..end];
+    is_name(name).then_some((name, end))
+}
+
+fn is_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/me".into()),
+            "API_HOST" => Some("api.local".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expands_plain_and_braced_variables() {
+        let out = expand_env("cd $HOME && curl ${API_HOST}/v1", lookup, false);
+        assert_eq!(out.unwrap(), "cd /home/me && curl api.local/v1");
+    }
+
+    #[test]
+    fn leaves_unknown_variables_untouched() {
+        let out = expand_env("echo $NOPE ${NOPE} $HOME", lookup, false);
+        assert_eq!(out.unwrap(), "echo $NOPE ${NOPE} /home/me");
+    }
+
+    #[test]
+    fn strict_mode_reports_unknown_variables() {
+        let err = expand_env("echo $NOPE ${NOPE} $ALSO_NOPE", lookup, true);
+        assert_eq!(
+            err,
+            Err(UnsetVariables(vec!["NOPE".into(), "ALSO_NOPE".into()]))
+        );
+    }
+
+    #[test]
+    fn ignores_dollars_that_are_not_variables() {
+        let out = expand_env("cost: $5, $ alone, ${unterminated", lookup, true);
+        assert_eq!(out.unwrap(), "cost: $5, $ alone, ${unterminated");
+    }
+}
@@ -0,0 +1,78 @@
+pub fn rank<'a>(query: &str, names: &[&'a str]) -> Vec<&'a str> {
+    let mut scored: Vec<(i64, &str)> = names
+        .iter()
+        .filter_map(|name| Some((score(query, name)?, *name)))
+        .collect();
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then(a.chars().count().cmp(&b.chars().count()))
+            .then(a.cmp(b))
+    });
+    scored.into_iter().map(|(_, name)| name).collect()
+}
+
+// Query characters must appear in order; runs and word starts score higher.
+fn score(query: &str, name: &str) -> Option<i64> {
+    let mut wanted = query.chars().filter(|c| !c.is_whitespace()).peekable();
+    let mut score = 0;
+    let mut previous_match = None;
+    let mut previous_char = None;
+
+    for (i, c) in name.chars().enumerate() {
+        let Some(&want) = wanted.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(want.to_lowercase()) {
+            score += 1;
+            if previous_match.is_some_and(|p| p + 1 == i) {
+                score += 5;
+            }
+            if previous_char.is_none_or(|p: char| matches!(p, ' ' | '-' | '_' | '/' | '.')) {
+                score += 3;
+            }
+            if previous_match.is_none() {
+                score -= i as i64;
+            }
+            previous_match = Some(i);
+            wanted.next();
+        }
+        previous_char = Some(c);
+    }
+
+    wanted.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_names_containing_the_query_in_order() {
+        let names = ["deploy", "docker build", "yolo"];
+        assert_eq!(rank("dpl", &names), ["deploy"]);
+        assert!(rank("xyz", &names).is_empty());
+    }
+
+    #[test]
+    fn contiguous_matches_rank_first() {
+        let names = ["read-all-config", "cfg parser", "config"];
+        assert_eq!(rank("conf", &names), ["config", "read-all-config"]);
+    }
+
+    #[test]
+    fn word_starts_beat_mid_word_matches() {
+        let names = ["docker-run", "mydocs"];
+        assert_eq!(rank("doc", &names), ["docker-run", "mydocs"]);
+    }
+
+    #[test]
+    fn matching_is_case_insensitive() {
+        assert_eq!(rank("RUST", &["cool rust pattern"]), ["cool rust pattern"]);
+    }
+
+    #[test]
+    fn empty_query_keeps_every_name() {
+        assert_eq!(rank("", &["b", "a"]), ["a", "b"]);
+    }
+}
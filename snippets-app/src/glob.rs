@@ -0,0 +1,56 @@
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_pattern_matches_exactly() {
+        assert!(matches("tmp", "tmp"));
+        assert!(!matches("tmp", "tmp-1"));
+    }
+
+    #[test]
+    fn star_matches_any_run() {
+        assert!(matches("tmp-*", "tmp-"));
+        assert!(matches("tmp-*", "tmp-scratch"));
+        assert!(matches("*rust*", "cool rust pattern"));
+        assert!(matches("a*b*c", "aXXbYYc"));
+        assert!(!matches("tmp-*", "keep-tmp-1"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_char() {
+        assert!(matches("v?", "v1"));
+        assert!(matches("v?", "vé"));
+        assert!(!matches("v?", "v10"));
+    }
+}
@@ -0,0 +1,1968 @@
+mod base64;
+mod checksum;
+pub mod clipboard;
+pub mod date;
+pub mod diff;
+pub mod edit;
+mod error;
+pub mod expand;
+pub mod fuzzy;
+pub mod glob;
+pub mod table;
+pub mod template;
+
+pub use error::SnippetError;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    /// Unix seconds; `None` for snippets saved before timestamps were kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<u64>,
+    /// `code` holds base64-encoded bytes rather than text.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+}
+
+impl fmt::Display for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} bytes", self.name, self.code.len())?;
+        if let Some(language) = &self.language {
+            write!(f, ", {language}")?;
+        }
+        if let Some(created_at) = self.created_at {
+            write!(f, ", created {}", date::format(created_at))?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredEntry")]
+struct Entry {
+    code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_accessed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_at: Option<u64>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    metadata: HashMap<String, String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    binary: bool,
+    checksum: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    history: Vec<Version>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Version {
+    pub code: String,
+    pub saved_at: u64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+}
+
+impl Entry {
+    fn new(code: String, language: Option<String>) -> Self {
+        let now = unix_now();
+        Entry {
+            checksum: checksum::sha256_hex(code.as_bytes()),
+            code,
+            language,
+            last_accessed: None,
+            created_at: Some(now),
+            updated_at: Some(now),
+            metadata: HashMap::new(),
+            binary: false,
+            history: Vec::new(),
+        }
+    }
+
+    fn set_code(&mut self, code: String, history_depth: usize) -> String {
+        if history_depth > 0 && code != self.code {
+            self.history.push(Version {
+                code: self.code.clone(),
+                saved_at: self.updated_at.unwrap_or_else(unix_now),
+                binary: self.binary,
+            });
+            let excess = self.history.len().saturating_sub(history_depth);
+            self.history.drain(..excess);
+        }
+        self.checksum = checksum::sha256_hex(code.as_bytes());
+        self.updated_at = Some(unix_now());
+        std::mem::replace(&mut self.code, code)
+    }
+
+    fn is_intact(&self) -> bool {
+        self.checksum == checksum::sha256_hex(self.code.as_bytes())
+    }
+}
+
+// Stores written before snippets had a language map names straight to code.
+// Entries saved before timestamps existed keep them unknown rather than
+// pretending they were created at load time.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredEntry {
+    Code(String),
+    Entry {
+        code: String,
+        #[serde(default)]
+        language: Option<String>,
+        #[serde(default)]
+        last_accessed: Option<u64>,
+        #[serde(default)]
+        created_at: Option<u64>,
+        #[serde(default)]
+        updated_at: Option<u64>,
+        #[serde(default)]
+        metadata: HashMap<String, String>,
+        #[serde(default)]
+        binary: bool,
+        #[serde(default)]
+        checksum: Option<String>,
+        #[serde(default)]
+        history: Vec<Version>,
+    },
+}
+
+impl From<StoredEntry> for Entry {
+    fn from(stored: StoredEntry) -> Self {
+        match stored {
+            StoredEntry::Code(code) => Entry {
+                created_at: None,
+                updated_at: None,
+                ..Entry::new(code, None)
+            },
+            StoredEntry::Entry {
+                code,
+                language,
+                last_accessed,
+                created_at,
+                updated_at,
+                metadata,
+                binary,
+                checksum: Some(checksum),
+                history,
+            } => Entry {
+                code,
+                language,
+                last_accessed,
+                created_at,
+                updated_at,
+                metadata,
+                binary,
+                checksum,
+                history,
+            },
+            StoredEntry::Entry {
+                code,
+                language,
+                last_accessed,
+                created_at,
+                updated_at,
+                metadata,
+                binary,
+                checksum: None,
+                history,
+            } => Entry {
+                last_accessed,
+                created_at,
+                updated_at,
+                metadata,
+                binary,
+                history,
+                ..Entry::new(code, language)
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SnippetStore {
+    snippets: HashMap<String, Entry>,
+    #[serde(skip)]
+    read_only: bool,
+    #[serde(skip)]
+    history_depth: usize,
+    #[serde(skip)]
+    recovery: Option<Recovery>,
+}
+
+/// A corrupted store that loading moved aside before starting empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovery {
+    pub backup: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+impl FromStr for ImportMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(ImportMode::Skip),
+            "overwrite" => Ok(ImportMode::Overwrite),
+            "rename" => Ok(ImportMode::Rename),
+            other => Err(format!(
+                "unknown import mode '{other}' (expected skip, overwrite or rename)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Stats {
+    pub total: usize,
+    pub total_bytes: usize,
+    pub average_bytes: f64,
+    pub per_language: BTreeMap<String, usize>,
+    pub without_language: usize,
+    /// Name and `created_at` of the oldest and newest snippets, ignoring
+    /// those without a creation time.
+    pub oldest: Option<(String, u64)>,
+    pub newest: Option<(String, u64)>,
+}
+
+pub fn stats(snippets: &[Snippet]) -> Stats {
+    let mut stats = Stats {
+        total: snippets.len(),
+        ..Stats::default()
+    };
+    for snippet in snippets {
+        stats.total_bytes += snippet.code.len();
+        match &snippet.language {
+            Some(language) => *stats.per_language.entry(language.clone()).or_default() += 1,
+            None => stats.without_language += 1,
+        }
+        if let Some(created_at) = snippet.created_at {
+            if stats.oldest.as_ref().is_none_or(|(_, at)| created_at < *at) {
+                stats.oldest = Some((snippet.name.clone(), created_at));
+            }
+            if stats.newest.as_ref().is_none_or(|(_, at)| created_at > *at) {
+                stats.newest = Some((snippet.name.clone(), created_at));
+            }
+        }
+    }
+    if stats.total > 0 {
+        stats.average_bytes = stats.total_bytes as f64 / stats.total as f64;
+    }
+    stats
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub renamed: usize,
+    pub skipped: usize,
+    pub too_large: usize,
+}
+
+// Accepts an --export array, a whole snippets.json, or a bare name-to-code map.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ImportFile {
+    List(Vec<Snippet>),
+    Store(SnippetStore),
+    Map(HashMap<String, Entry>),
+}
+
+pub fn read_import(path: &Path) -> io::Result<Vec<Snippet>> {
+    let content = fs::read_to_string(path)?;
+    let file: ImportFile = serde_json::from_str(&content).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a snippet export: {err}", path.display()),
+        )
+    })?;
+
+    Ok(match file {
+        ImportFile::List(snippets) => snippets,
+        ImportFile::Store(store) => store.list(),
+        ImportFile::Map(map) => SnippetStore {
+            snippets: map,
+            ..SnippetStore::default()
+        }
+        .list(),
+    })
+}
+
+impl SnippetStore {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let read_only = read_only_mode();
+        let recover = env::var("SNIPPETS_APP_RECOVER").is_ok_and(|value| value == "1");
+        let mut store = Self::load_with_recovery(path, recover && !read_only)?;
+        store.read_only = read_only;
+        // Only writes keep history, so a bad depth is reported by the write
+        // paths (see history_depth) instead of breaking every read.
+        store.history_depth = history_depth().unwrap_or(0);
+        Ok(store)
+    }
+
+    pub fn load_with_recovery(path: &Path, recover: bool) -> io::Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err),
+        };
+
+        match serde_json::from_str(&content) {
+            Ok(store) => Ok(store),
+            Err(err) if recover => Ok(SnippetStore {
+                recovery: Some(Recovery {
+                    backup: quarantine(path)?,
+                    error: err.to_string(),
+                }),
+                ..Self::default()
+            }),
+            Err(err) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} is corrupted: {err} (set SNIPPETS_APP_RECOVER=1 to move it aside and start empty)",
+                    path.display()
+                ),
+            )),
+        }
+    }
+
+    pub fn load_shared(path: &Path) -> io::Result<Self> {
+        let _lock = StoreLock::shared(path);
+        Self::load(path)
+    }
+
+    /// Set when this load found the store corrupted and moved it aside;
+    /// callers decide how to tell the user.
+    pub fn recovery(&self) -> Option<&Recovery> {
+        self.recovery.as_ref()
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let compact = env::var("SNIPPETS_APP_JSON_COMPACT").is_ok_and(|value| value == "1");
+        self.save_formatted(path, !compact)
+    }
+
+    pub fn save_formatted(&self, path: &Path, pretty: bool) -> io::Result<()> {
+        self.ensure_writable()?;
+        let data = if pretty {
+            serde_json::to_string_pretty(self)?
+        } else {
+            serde_json::to_string(self)?
+        };
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        let mut file = File::create(&tmp)?;
+        file.write_all(data.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history_depth = depth;
+    }
+
+    fn ensure_writable(&self) -> io::Result<()> {
+        if self.read_only {
+            return Err(read_only_error());
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.snippets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snippets.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.snippets.get(name).map(|entry| entry.code.as_str())
+    }
+
+    pub fn checked_get(&self, name: &str) -> Result<&str, SnippetError> {
+        let entry = self
+            .snippets
+            .get(name)
+            .ok_or_else(|| SnippetError::NotFound(name.to_string()))?;
+        if !entry.is_intact() {
+            return Err(SnippetError::ChecksumMismatch(name.to_string()));
+        }
+        Ok(&entry.code)
+    }
+
+    pub fn verify(&self) -> Vec<String> {
+        let mut corrupted: Vec<String> = self
+            .snippets
+            .iter()
+            .filter(|(_, entry)| !entry.is_intact())
+            .map(|(name, _)| name.clone())
+            .collect();
+        corrupted.sort();
+        corrupted
+    }
+
+    pub fn get_many(&self, names: &[&str]) -> Vec<Snippet> {
+        names.iter().filter_map(|name| self.snippet(name)).collect()
+    }
+
+    pub fn snippet(&self, name: &str) -> Option<Snippet> {
+        let entry = self.snippets.get(name)?;
+        Some(Snippet {
+            name: name.to_string(),
+            code: entry.code.clone(),
+            language: entry.language.clone(),
+            metadata: entry.metadata.clone(),
+            created_at: entry.created_at,
+            updated_at: entry.updated_at,
+            binary: entry.binary,
+        })
+    }
+
+    pub fn language(&self, name: &str) -> Option<&str> {
+        self.snippets.get(name)?.language.as_deref()
+    }
+
+    pub fn set_language(&mut self, name: &str, language: Option<String>) {
+        if let Some(entry) = self.snippets.get_mut(name) {
+            entry.language = language;
+        }
+    }
+
+    pub fn metadata(&self, name: &str) -> Option<&HashMap<String, String>> {
+        Some(&self.snippets.get(name)?.metadata)
+    }
+
+    // New values replace old ones for the same key; other keys are kept.
+    pub fn add_metadata(&mut self, name: &str, metadata: HashMap<String, String>) {
+        if let Some(entry) = self.snippets.get_mut(name) {
+            entry.metadata.extend(metadata);
+        }
+    }
+
+    pub fn last_accessed(&self, name: &str) -> Option<u64> {
+        self.snippets.get(name)?.last_accessed
+    }
+
+    pub fn created_at(&self, name: &str) -> Option<u64> {
+        self.snippets.get(name)?.created_at
+    }
+
+    pub fn updated_at(&self, name: &str) -> Option<u64> {
+        self.snippets.get(name)?.updated_at
+    }
+
+    pub fn touch(&mut self, name: &str) -> Result<(), SnippetError> {
+        let entry = self
+            .snippets
+            .get_mut(name)
+            .ok_or_else(|| SnippetError::NotFound(name.to_string()))?;
+        let now = unix_now();
+        entry.last_accessed = Some(now);
+        entry.updated_at = Some(now);
+        Ok(())
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.snippets.contains_key(name)
+    }
+
+    pub fn unique_name(&self, base: &str) -> String {
+        if !self.exists(base) {
+            return base.to_string();
+        }
+        (2..)
+            .map(|n| format!("{base}-{n}"))
+            .find(|candidate| !self.exists(candidate))
+            .expect("an unused suffix always exists")
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, code: impl Into<String>) -> Option<String> {
+        let name = name.into();
+        let code = code.into();
+        match self.snippets.get_mut(&name) {
+            Some(entry) => {
+                let old = entry.set_code(code, self.history_depth);
+                entry.binary = false;
+                Some(old)
+            }
+            None => {
+                self.snippets.insert(name, Entry::new(code, None));
+                None
+            }
+        }
+    }
+
+    /// Saves raw bytes, base64-encoded and marked as binary.
+    pub fn insert_bytes(&mut self, name: impl Into<String>, bytes: &[u8]) {
+        let name = name.into();
+        self.insert(name.clone(), base64::encode(bytes));
+        if let Some(entry) = self.snippets.get_mut(&name) {
+            entry.binary = true;
+        }
+    }
+
+    pub fn is_binary(&self, name: &str) -> bool {
+        self.snippets.get(name).is_some_and(|entry| entry.binary)
+    }
+
+    /// The snippet's body as bytes: decoded for binary snippets, the UTF-8
+    /// text otherwise.
+    pub fn checked_get_bytes(&self, name: &str) -> Result<Vec<u8>, SnippetError> {
+        let code = self.checked_get(name)?;
+        if !self.is_binary(name) {
+            return Ok(code.as_bytes().to_vec());
+        }
+        base64::decode(code).ok_or_else(|| {
+            SnippetError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("snippet '{name}' is marked binary but is not valid base64"),
+            ))
+        })
+    }
+
+    pub fn insert_snippet(&mut self, snippet: Snippet) {
+        match self.snippets.get_mut(&snippet.name) {
+            Some(entry) => {
+                entry.set_code(snippet.code, self.history_depth);
+                entry.binary = snippet.binary;
+                if snippet.language.is_some() {
+                    entry.language = snippet.language;
+                }
+                entry.metadata.extend(snippet.metadata);
+            }
+            None => {
+                let mut entry = Entry::new(snippet.code, snippet.language);
+                entry.metadata = snippet.metadata;
+                entry.binary = snippet.binary;
+                // Keep the timestamps of snippets carried over from elsewhere.
+                if snippet.created_at.is_some() {
+                    entry.created_at = snippet.created_at;
+                    entry.updated_at = snippet.updated_at.or(snippet.created_at);
+                }
+                self.snippets.insert(snippet.name, entry);
+            }
+        }
+    }
+
+    pub fn append(&mut self, name: &str, code: &str) -> Result<(), SnippetError> {
+        match self.snippets.get_mut(name) {
+            Some(entry) if !entry.is_intact() => {
+                return Err(SnippetError::ChecksumMismatch(name.to_string()));
+            }
+            Some(entry) if entry.binary => {
+                return Err(SnippetError::Binary(name.to_string()));
+            }
+            Some(entry) => {
+                let mut combined = entry.code.clone();
+                if !combined.is_empty() && !combined.ends_with('\n') {
+                    combined.push('\n');
+                }
+                combined.push_str(code);
+                entry.set_code(combined, self.history_depth);
+            }
+            None => {
+                self.insert(name, code);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn history(&self, name: &str) -> Option<Vec<Version>> {
+        let entry = self.snippets.get(name)?;
+        Some(entry.history.iter().rev().cloned().collect())
+    }
+
+    // Versions count back from the most recent, starting at 1.
+    pub fn restore(&mut self, name: &str, version: usize) -> Result<(), SnippetError> {
+        let entry = self
+            .snippets
+            .get_mut(name)
+            .ok_or_else(|| SnippetError::NotFound(name.to_string()))?;
+        let index = entry
+            .history
+            .len()
+            .checked_sub(version)
+            .filter(|_| version > 0)
+            .ok_or_else(|| SnippetError::NoSuchVersion(name.to_string(), version))?;
+
+        // The body being replaced always goes into history, even with no
+        // depth configured, so a restore can itself be undone.
+        let restored = entry.history.remove(index);
+        let depth = self.history_depth.max(entry.history.len() + 1);
+        entry.set_code(restored.code, depth);
+        entry.binary = restored.binary;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.snippets.remove(name).map(|entry| entry.code)
+    }
+
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), SnippetError> {
+        self.ensure_writable()?;
+        if self.exists(new) {
+            return Err(SnippetError::AlreadyExists(new.to_string()));
+        }
+        let entry = self
+            .snippets
+            .remove(old)
+            .ok_or_else(|| SnippetError::NotFound(old.to_string()))?;
+        self.snippets.insert(new.to_string(), entry);
+        Ok(())
+    }
+
+    pub fn delete_many(&mut self, names: &[&str]) -> usize {
+        names
+            .iter()
+            .filter(|name| self.snippets.remove(**name).is_some())
+            .count()
+    }
+
+    pub fn delete_prefix(&mut self, prefix: &str) -> usize {
+        let before = self.snippets.len();
+        self.snippets.retain(|name, _| !name.starts_with(prefix));
+        before - self.snippets.len()
+    }
+
+    pub fn clear(&mut self) -> usize {
+        let cleared = self.snippets.len();
+        self.snippets.clear();
+        cleared
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.snippets.keys().map(String::as_str)
+    }
+
+    pub fn search(&self, query: &str) -> Vec<Snippet> {
+        let query = query.to_lowercase();
+        let mut found = self.list();
+        found.retain(|snippet| {
+            snippet.name.to_lowercase().contains(&query)
+                || snippet.code.to_lowercase().contains(&query)
+        });
+        found
+    }
+
+    pub fn import(
+        &mut self,
+        snippets: Vec<Snippet>,
+        mode: ImportMode,
+        max_bytes: u64,
+    ) -> ImportSummary {
+        let mut summary = ImportSummary::default();
+
+        for mut snippet in snippets {
+            if snippet.code.len() as u64 > max_bytes {
+                summary.too_large += 1;
+                continue;
+            }
+            if !self.exists(&snippet.name) {
+                summary.imported += 1;
+            } else {
+                match mode {
+                    ImportMode::Skip => {
+                        summary.skipped += 1;
+                        continue;
+                    }
+                    ImportMode::Overwrite => summary.imported += 1,
+                    ImportMode::Rename => {
+                        snippet.name = self.unique_name(&snippet.name);
+                        summary.renamed += 1;
+                    }
+                }
+            }
+            self.insert_snippet(snippet);
+        }
+        summary
+    }
+
+    pub fn export(&self, path: &Path) -> Result<usize, SnippetError> {
+        let snippets = self.checked_list()?;
+        fs::write(
+            path,
+            serde_json::to_string_pretty(&snippets).map_err(io::Error::from)?,
+        )?;
+        Ok(snippets.len())
+    }
+
+    pub fn checked_list(&self) -> Result<Vec<Snippet>, SnippetError> {
+        match self.verify().into_iter().next() {
+            Some(corrupted) => Err(SnippetError::ChecksumMismatch(corrupted)),
+            None => Ok(self.list()),
+        }
+    }
+
+    /// The most recently created snippet. Entries without a creation time
+    /// are never picked; ties go to the name that sorts last.
+    pub fn latest(&self) -> Option<Snippet> {
+        let (name, _) = self
+            .snippets
+            .iter()
+            .filter_map(|(name, entry)| Some((name, entry.created_at?)))
+            .max_by_key(|&(name, created_at)| (created_at, name))?;
+        self.snippet(name)
+    }
+
+    pub fn list(&self) -> Vec<Snippet> {
+        let mut snippets: Vec<Snippet> = self
+            .snippets
+            .keys()
+            .filter_map(|name| self.snippet(name))
+            .collect();
+        snippets.sort_by(|a, b| a.name.cmp(&b.name));
+        snippets
+    }
+
+    pub fn list_page(&self, offset: usize, limit: usize) -> Vec<Snippet> {
+        self.list().into_iter().skip(offset).take(limit).collect()
+    }
+}
+
+/// Advisory lock on a store file, held until dropped.
+///
+/// Locks live on a sibling `<file>.lock` so they survive the store file
+/// being replaced. They only coordinate processes that also take them, and
+/// may be ignored entirely on network filesystems such as NFS or SMB.
+///
+/// A shared lock is best effort: readers that can't open or create the lock
+/// file, e.g. in a directory they can't write to, read without one.
+pub struct StoreLock {
+    _file: File,
+}
+
+impl StoreLock {
+    pub fn exclusive(path: &Path) -> io::Result<Self> {
+        let file = Self::open(path)?;
+        file.lock()?;
+        Ok(StoreLock { _file: file })
+    }
+
+    pub fn shared(path: &Path) -> Option<Self> {
+        let file = File::open(Self::lock_path(path))
+            .or_else(|_| Self::open(path))
+            .ok()?;
+        file.lock_shared().ok()?;
+        Some(StoreLock { _file: file })
+    }
+
+    /// A shared lock that never creates the lock file, for dry runs that
+    /// must leave the directory as they found it. Saves swap the store in
+    /// with a rename, so reading without a lock still sees a whole file.
+    pub fn shared_existing(path: &Path) -> Option<Self> {
+        let file = File::open(Self::lock_path(path)).ok()?;
+        file.lock_shared().ok()?;
+        Some(StoreLock { _file: file })
+    }
+
+    fn open(path: &Path) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(Self::lock_path(path))
+    }
+
+    fn lock_path(path: &Path) -> PathBuf {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        PathBuf::from(lock_path)
+    }
+}
+
+pub fn matching_line<'a>(code: &'a str, query: &str) -> Option<&'a str> {
+    let query = query.to_lowercase();
+    code.lines()
+        .find(|line| line.to_lowercase().contains(&query))
+        .map(str::trim)
+}
+
+pub fn confirm(mut input: impl BufRead, mut output: impl Write, prompt: &str) -> io::Result<bool> {
+    write!(output, "{prompt} [y/N] ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+}
+
+pub fn number_lines(body: &str) -> String {
+    let width = body.lines().count().to_string().len();
+    body.lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:>width$} | {line}\n", i + 1))
+        .collect()
+}
+
+pub const MAX_AUTO_NAME_CHARS: usize = 60;
+
+pub fn name_from_code(code: &str) -> Option<String> {
+    let line = code.lines().map(str::trim).find(|line| !line.is_empty())?;
+    Some(
+        line.chars()
+            .take(MAX_AUTO_NAME_CHARS)
+            .collect::<String>()
+            .trim_end()
+            .to_string(),
+    )
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".corrupt-{}", unix_now()));
+
+    let backup = PathBuf::from(backup);
+    fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+pub fn storage_path() -> PathBuf {
+    PathBuf::from("snippets.json")
+}
+
+pub fn max_snippet_bytes() -> io::Result<u64> {
+    match env::var("SNIPPETS_APP_MAX_BYTES") {
+        Ok(value) => value.parse().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("SNIPPETS_APP_MAX_BYTES must be a number of bytes, got '{value}'"),
+            )
+        }),
+        Err(_) => Ok(DEFAULT_MAX_BYTES),
+    }
+}
+
+pub fn read_only_mode() -> bool {
+    env::var("SNIPPETS_APP_READ_ONLY").is_ok_and(|value| value == "1")
+}
+
+pub fn read_only_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "the snippet store is read-only (unset SNIPPETS_APP_READ_ONLY to modify it)",
+    )
+}
+
+pub fn history_depth() -> io::Result<usize> {
+    match env::var("SNIPPETS_APP_HISTORY_DEPTH") {
+        Ok(value) => value.parse().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("SNIPPETS_APP_HISTORY_DEPTH must be a number of versions, got '{value}'"),
+            )
+        }),
+        Err(_) => Ok(0),
+    }
+}
+
+pub fn too_large(actual: u64, max_bytes: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("snippet is {actual} bytes, which exceeds the limit of {max_bytes} bytes"),
+    )
+}
+
+// Used where the input is cut off at the limit, so its full size is unknown.
+fn more_than(what: &str, max_bytes: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{what} is more than {max_bytes} bytes, which exceeds the limit"),
+    )
+}
+
+pub fn read_code(reader: impl Read, max_bytes: u64) -> io::Result<String> {
+    let bytes = read_bytes(reader, max_bytes)?;
+    String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn read_bytes(reader: impl Read, max_bytes: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader
+        .take(max_bytes.saturating_add(1))
+        .read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 > max_bytes {
+        return Err(more_than("snippet", max_bytes));
+    }
+    Ok(bytes)
+}
+
+/// Reads one line, without its line ending, buffering at most `max_bytes`.
+///
+/// Returns `Ok(None)` at the end of the input. A line over the limit is
+/// skipped up to its newline and reported in the inner error, so callers
+/// can carry on with the next line; the outer error is a failed read.
+pub fn read_bounded_line(
+    reader: &mut impl BufRead,
+    max_bytes: u64,
+) -> io::Result<Option<io::Result<Vec<u8>>>> {
+    let mut line = Vec::new();
+    // Two extra bytes leave room for a CRLF after a line right at the limit.
+    let read = reader
+        .take(max_bytes.saturating_add(2))
+        .read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+
+    let ended = line.last() == Some(&b'\n');
+    if ended {
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    }
+    if line.len() as u64 > max_bytes {
+        if !ended {
+            skip_line(reader)?;
+        }
+        return Ok(Some(Err(more_than("line", max_bytes))));
+    }
+    Ok(Some(Ok(line)))
+}
+
+fn skip_line(reader: &mut impl BufRead) -> io::Result<()> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        match buffer.iter().position(|&byte| byte == b'\n') {
+            Some(end) => {
+                reader.consume(end + 1);
+                return Ok(());
+            }
+            None => {
+                let len = buffer.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("snippets-app-{name}-{}.json", std::process::id()))
+    }
+
+    pub(crate) fn snippet(name: &str, code: &str, language: Option<&str>) -> Snippet {
+        Snippet {
+            name: name.into(),
+            code: code.into(),
+            language: language.map(String::from),
+            metadata: HashMap::new(),
+            created_at: None,
+            updated_at: None,
+            binary: false,
+        }
+    }
+
+    #[test]
+    fn snippet_displays_as_one_line_summary() {
+        let mut snippet = snippet("hello", "fn main() {}\n", None);
+        assert_eq!(snippet.to_string(), "hello (13 bytes)");
+
+        snippet.language = Some("rust".into());
+        assert_eq!(snippet.to_string(), "hello (13 bytes, rust)");
+
+        snippet.created_at = Some(0);
+        assert_eq!(
+            snippet.to_string(),
+            "hello (13 bytes, rust, created 1970-01-01T00:00:00Z)"
+        );
+    }
+
+    #[test]
+    fn store_written_before_languages_still_loads() {
+        let path = temp_path("legacy");
+        fs::write(&path, r#"{"snippets": {"old": "echo hi"}}"#).unwrap();
+
+        let store = SnippetStore::load(&path).unwrap();
+        assert_eq!(store.get("old"), Some("echo hi"));
+        assert_eq!(store.language("old"), None);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn language_survives_save_and_code_updates() {
+        let path = temp_path("language");
+        let mut store = SnippetStore::default();
+        store.insert("query", "SELECT 1;");
+        store.set_language("query", Some("sql".into()));
+        store.insert("query", "SELECT 2;");
+        store.save(&path).unwrap();
+
+        let loaded = SnippetStore::load(&path).unwrap();
+        assert_eq!(loaded.get("query"), Some("SELECT 2;"));
+        assert_eq!(loaded.language("query"), Some("sql"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn touch_records_access_without_changing_code() {
+        let path = temp_path("touch");
+        let code = "let x = 1;\r\n\tlet y = 2; // é";
+        let mut store = SnippetStore::default();
+        store.insert("vars", code);
+        assert_eq!(store.last_accessed("vars"), None);
+
+        store.touch("vars").unwrap();
+        store.save(&path).unwrap();
+
+        let loaded = SnippetStore::load(&path).unwrap();
+        assert_eq!(loaded.get("vars").unwrap().as_bytes(), code.as_bytes());
+        assert!(loaded.last_accessed("vars").is_some());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn resaving_updates_updated_at_but_keeps_created_at() {
+        let mut store = SnippetStore::default();
+        store.insert("a", "one");
+        let entry = store.snippets.get_mut("a").unwrap();
+        entry.created_at = Some(1);
+        entry.updated_at = Some(1);
+
+        store.insert("a", "two");
+        assert_eq!(store.created_at("a"), Some(1));
+        assert!(store.updated_at("a") > Some(1));
+
+        store.snippets.get_mut("a").unwrap().updated_at = Some(1);
+        store.append("a", "three").unwrap();
+        assert!(store.updated_at("a") > Some(1));
+
+        store.snippets.get_mut("a").unwrap().updated_at = Some(1);
+        store.touch("a").unwrap();
+        assert_eq!(store.get("a"), Some("two\nthree"));
+        assert!(store.updated_at("a") > Some(1));
+        assert_eq!(store.created_at("a"), Some(1));
+    }
+
+    #[test]
+    fn entries_without_timestamps_load_as_unknown() {
+        let path = temp_path("no-timestamps");
+        fs::write(
+            &path,
+            r#"{"snippets": {"old": "echo old", "lang": {"code": "x", "language": "sh"}}}"#,
+        )
+        .unwrap();
+
+        let store = SnippetStore::load(&path).unwrap();
+        assert_eq!(store.created_at("old"), None);
+        assert_eq!(store.updated_at("old"), None);
+        assert_eq!(store.created_at("lang"), None);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn metadata_is_saved_merged_and_defaults_to_empty() {
+        let path = temp_path("metadata");
+        fs::write(&path, r#"{"snippets": {"old": "echo old"}}"#).unwrap();
+        let mut store = SnippetStore::load(&path).unwrap();
+        assert_eq!(store.metadata("old"), Some(&HashMap::new()));
+
+        let pairs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        store.add_metadata("old", pairs(&[("author", "me"), ("source_url", "a")]));
+        store.add_metadata("old", pairs(&[("source_url", "b")]));
+        store.save(&path).unwrap();
+
+        let loaded = SnippetStore::load(&path).unwrap();
+        assert_eq!(
+            loaded.metadata("old"),
+            Some(&pairs(&[("author", "me"), ("source_url", "b")]))
+        );
+        assert_eq!(loaded.list()[0].metadata["author"], "me");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn touch_of_missing_snippet_fails() {
+        let mut store = SnippetStore::default();
+        let err = store.touch("missing").unwrap_err();
+        assert!(matches!(err, SnippetError::NotFound(name) if name == "missing"));
+    }
+
+    #[test]
+    fn read_only_store_refuses_writes_but_still_reads() {
+        let path = temp_path("read-only");
+        let mut store = SnippetStore::default();
+        store.insert("keep", "echo keep");
+        store.set_read_only(true);
+
+        assert_eq!(store.get("keep"), Some("echo keep"));
+        let err = store.save(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(!path.exists());
+
+        let err = store.rename("keep", "moved").unwrap_err();
+        assert!(
+            matches!(err, SnippetError::Io(err) if err.kind() == io::ErrorKind::PermissionDenied)
+        );
+        assert!(store.exists("keep"));
+    }
+
+    #[test]
+    fn corrupted_code_fails_checksum_verification() {
+        let mut store = SnippetStore::default();
+        store.insert("good", "echo good");
+        store.insert("bad", "echo bad");
+        store.append("bad", "echo more").unwrap();
+        assert_eq!(store.checked_get("bad").unwrap(), "echo bad\necho more");
+        assert!(store.verify().is_empty());
+
+        store.snippets.get_mut("bad").unwrap().code.push('!');
+
+        assert_eq!(store.verify(), vec!["bad".to_string()]);
+        assert!(matches!(
+            store.checked_get("bad"),
+            Err(SnippetError::ChecksumMismatch(name)) if name == "bad"
+        ));
+        assert_eq!(store.checked_get("good").unwrap(), "echo good");
+    }
+
+    #[test]
+    fn corrupted_code_is_not_appended_to_or_exported() {
+        let path = temp_path("export-corrupted");
+        let mut store = SnippetStore::default();
+        store.insert("bad", "echo bad");
+        store.snippets.get_mut("bad").unwrap().code.push('!');
+
+        assert!(matches!(
+            store.append("bad", "echo more"),
+            Err(SnippetError::ChecksumMismatch(_))
+        ));
+        assert_eq!(store.verify(), vec!["bad".to_string()]);
+        assert!(store.checked_list().is_err());
+        assert!(matches!(
+            store.export(&path),
+            Err(SnippetError::ChecksumMismatch(_))
+        ));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn save_and_load_roundtrip() {
+        let path = temp_path("roundtrip");
+        let mut store = SnippetStore::default();
+        store.insert("hello", "println!(\"hi\");");
+        store.save(&path).unwrap();
+
+        let loaded = SnippetStore::load(&path).unwrap();
+        assert_eq!(loaded.get("hello"), Some("println!(\"hi\");"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn save_replaces_a_stale_temp_file() {
+        let path = temp_path("atomic");
+        let tmp = PathBuf::from(format!("{}.tmp", path.display()));
+        fs::write(&tmp, "{ half written").unwrap();
+
+        let mut store = SnippetStore::default();
+        store.insert("a", "1");
+        store.save(&path).unwrap();
+
+        assert!(!tmp.exists());
+        assert_eq!(SnippetStore::load(&path).unwrap().get("a"), Some("1"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn concurrent_locked_saves_keep_both_snippets() {
+        let path = temp_path("concurrent");
+        let writers: Vec<_> = ["first", "second"]
+            .into_iter()
+            .map(|name| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let _lock = StoreLock::exclusive(&path).unwrap();
+                    let mut store = SnippetStore::load(&path).unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    store.insert(name, name);
+                    store.save(&path).unwrap();
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let store = SnippetStore::load(&path).unwrap();
+        assert_eq!(store.get("first"), Some("first"));
+        assert_eq!(store.get("second"), Some("second"));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(format!("{}.lock", path.display())).unwrap();
+    }
+
+    #[test]
+    fn shared_load_works_without_a_lock_file() {
+        let dir = env::temp_dir().join(format!("snippets-app-missing-dir-{}", std::process::id()));
+        let store = SnippetStore::load_shared(&dir.join("snippets.json")).unwrap();
+        assert!(store.is_empty());
+        assert!(!dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shared_load_works_in_a_read_only_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("snippets-app-ro-dir-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("snippets.json");
+        let mut store = SnippetStore::default();
+        store.insert("a", "echo a");
+        store.save(&path).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+        let loaded = SnippetStore::load_shared(&path);
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(loaded.unwrap().get("a"), Some("echo a"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn compact_and_pretty_files_both_load() {
+        let mut store = SnippetStore::default();
+        store.insert("a", "1");
+        store.insert("b", "2");
+
+        for pretty in [true, false] {
+            let path = temp_path(&format!("format-{pretty}"));
+            store.save_formatted(&path, pretty).unwrap();
+            let content = fs::read_to_string(&path).unwrap();
+            assert_eq!(content.contains('\n'), pretty);
+
+            let loaded = SnippetStore::load(&path).unwrap();
+            assert_eq!(loaded.get("a"), Some("1"));
+            assert_eq!(loaded.get("b"), Some("2"));
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn latest_is_the_last_created_snippet() {
+        let store: SnippetStore = serde_json::from_str(
+            r#"{"snippets": {
+                "legacy": "echo",
+                "first": {"code": "1", "created_at": 10, "updated_at": 50},
+                "second": {"code": "2", "created_at": 20}
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!(store.latest().unwrap().name, "second");
+
+        let legacy: SnippetStore =
+            serde_json::from_str(r#"{"snippets": {"old": "echo"}}"#).unwrap();
+        assert_eq!(legacy.latest(), None);
+        assert_eq!(SnippetStore::default().latest(), None);
+    }
+
+    #[test]
+    fn list_returns_snippets_sorted_by_name() {
+        let path = temp_path("list");
+        let mut store = SnippetStore::default();
+        store.insert("zeta", "z");
+        store.insert("alpha", "a");
+        store.insert("mid", "m");
+        store.save(&path).unwrap();
+
+        let names: Vec<String> = SnippetStore::load(&path)
+            .unwrap()
+            .list()
+            .into_iter()
+            .map(|snippet| snippet.name)
+            .collect();
+        assert_eq!(names, ["alpha", "mid", "zeta"]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn consecutive_pages_reproduce_the_full_list() {
+        let mut store = SnippetStore::default();
+        for n in [7, 3, 9, 1, 5, 8, 2] {
+            store.insert(format!("snippet-{n}"), format!("code {n}"));
+        }
+
+        let pages: Vec<Snippet> = (0..store.len())
+            .step_by(3)
+            .flat_map(|offset| store.list_page(offset, 3))
+            .collect();
+        assert_eq!(pages, store.list());
+        assert!(store.list_page(store.len(), 3).is_empty());
+    }
+
+    #[test]
+    fn search_matches_names_and_code_case_insensitively() {
+        let mut store = SnippetStore::default();
+        store.insert("HTTP client", "let c = reqwest::Client::new();");
+        store.insert("server", "use axum::Router;\nlet app = Router::new();");
+        store.insert("unrelated", "println!(\"hi\");");
+
+        let names = |found: Vec<Snippet>| -> Vec<String> {
+            found.into_iter().map(|snippet| snippet.name).collect()
+        };
+        assert_eq!(names(store.search("REQWEST")), ["HTTP client"]);
+        assert_eq!(names(store.search("http")), ["HTTP client"]);
+        assert_eq!(names(store.search("router")), ["server"]);
+        assert!(store.search("tokio").is_empty());
+    }
+
+    #[test]
+    fn search_is_unicode_case_insensitive() {
+        let mut store = SnippetStore::default();
+        store.insert("café", "// ÉCOLE");
+        assert_eq!(store.search("CAFÉ").len(), 1);
+        assert_eq!(store.search("école").len(), 1);
+    }
+
+    #[test]
+    fn matching_line_finds_first_hit() {
+        let code = "use axum::Router;\n    let app = Router::new();";
+        assert_eq!(matching_line(code, "let"), Some("let app = Router::new();"));
+        assert_eq!(matching_line(code, "tokio"), None);
+    }
+
+    #[test]
+    fn len_matches_after_saves_and_a_delete() {
+        let path = temp_path("count");
+        let mut store = SnippetStore::default();
+        store.insert("a", "1");
+        store.insert("b", "2");
+        store.insert("c", "3");
+        store.save(&path).unwrap();
+
+        let mut store = SnippetStore::load(&path).unwrap();
+        assert_eq!(store.len(), 3);
+        store.remove("b");
+        store.save(&path).unwrap();
+
+        assert_eq!(SnippetStore::load(&path).unwrap().len(), 2);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn export_writes_every_snippet_as_a_json_array() {
+        let path = temp_path("export");
+        let mut store = SnippetStore::default();
+        store.insert("b", "2");
+        store.insert("a", "1");
+        store.set_language("a", Some("rust".into()));
+
+        assert_eq!(store.export(&path).unwrap(), 2);
+        let exported: Vec<Snippet> =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(exported, store.list());
+        fs::remove_file(path).unwrap();
+    }
+
+    fn conflicting_import() -> (SnippetStore, Vec<Snippet>) {
+        let mut store = SnippetStore::default();
+        store.insert("shared", "old");
+        let incoming = vec![
+            snippet("shared", "new", None),
+            snippet("fresh", "fresh", None),
+        ];
+        (store, incoming)
+    }
+
+    #[test]
+    fn import_skip_keeps_existing_snippets() {
+        let (mut store, incoming) = conflicting_import();
+        let summary = store.import(incoming, ImportMode::Skip, DEFAULT_MAX_BYTES);
+
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 1,
+                renamed: 0,
+                skipped: 1,
+                too_large: 0,
+            }
+        );
+        assert_eq!(store.get("shared"), Some("old"));
+        assert_eq!(store.get("fresh"), Some("fresh"));
+    }
+
+    #[test]
+    fn import_overwrite_replaces_existing_snippets() {
+        let (mut store, incoming) = conflicting_import();
+        let summary = store.import(incoming, ImportMode::Overwrite, DEFAULT_MAX_BYTES);
+
+        assert_eq!(summary.imported, 2);
+        assert_eq!(store.get("shared"), Some("new"));
+    }
+
+    #[test]
+    fn import_rename_adds_a_suffix_on_collision() {
+        let (mut store, incoming) = conflicting_import();
+        let summary = store.import(incoming, ImportMode::Rename, DEFAULT_MAX_BYTES);
+
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 1,
+                renamed: 1,
+                skipped: 0,
+                too_large: 0,
+            }
+        );
+        assert_eq!(store.get("shared"), Some("old"));
+        assert_eq!(store.get("shared-2"), Some("new"));
+    }
+
+    #[test]
+    fn import_leaves_out_snippets_over_the_size_limit() {
+        let (mut store, mut incoming) = conflicting_import();
+        incoming.push(snippet("huge", &"x".repeat(10), None));
+        let summary = store.import(incoming, ImportMode::Overwrite, 5);
+
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.too_large, 1);
+        assert!(!store.exists("huge"));
+    }
+
+    #[test]
+    fn export_then_import_roundtrips() {
+        let path = temp_path("export-import");
+        let mut source = SnippetStore::default();
+        source.insert("a", "1");
+        source.insert("b", "2");
+        source.set_language("b", Some("sh".into()));
+        source.export(&path).unwrap();
+
+        let mut target = SnippetStore::default();
+        target.import(
+            read_import(&path).unwrap(),
+            ImportMode::Skip,
+            DEFAULT_MAX_BYTES,
+        );
+        assert_eq!(target.list(), source.list());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn listed_snippets_carry_their_timestamps() {
+        let store: SnippetStore = serde_json::from_str(
+            r#"{"snippets": {"old": "echo", "new": {"code": "x", "created_at": 5, "updated_at": 9}}}"#,
+        )
+        .unwrap();
+        let stamps = |store: &SnippetStore| -> Vec<(String, Option<u64>, Option<u64>)> {
+            store
+                .list()
+                .into_iter()
+                .map(|snippet| (snippet.name, snippet.created_at, snippet.updated_at))
+                .collect()
+        };
+        assert_eq!(
+            stamps(&store),
+            [("new".into(), Some(5), Some(9)), ("old".into(), None, None)]
+        );
+        assert_eq!(store.get_many(&["new"])[0].created_at, Some(5));
+
+        let mut copy = SnippetStore::default();
+        copy.import(store.list(), ImportMode::Skip, u64::MAX);
+        assert_eq!(stamps(&copy)[0], ("new".into(), Some(5), Some(9)));
+        // Legacy entries have nothing to carry over, so they start now.
+        assert!(stamps(&copy)[1].1.is_some());
+    }
+
+    #[test]
+    fn binary_snippets_round_trip_through_base64() {
+        let path = temp_path("binary");
+        let bytes = [0xff, 0x00, 0xfe, b'\n'];
+        let mut store = SnippetStore::default();
+        store.insert_bytes("blob", &bytes);
+        store.save(&path).unwrap();
+
+        let mut store = SnippetStore::load(&path).unwrap();
+        assert!(store.is_binary("blob"));
+        assert_eq!(store.get("blob"), Some("/wD+Cg=="));
+        assert_eq!(store.checked_get_bytes("blob").unwrap(), bytes);
+        assert!(store.snippet("blob").unwrap().binary);
+        assert!(matches!(
+            store.append("blob", "text"),
+            Err(SnippetError::Binary(_))
+        ));
+
+        // Loading takes the depth from the environment, not the file.
+        store.set_history_depth(5);
+        store.insert("blob", "now text");
+        assert!(!store.is_binary("blob"));
+        assert_eq!(store.checked_get_bytes("blob").unwrap(), b"now text");
+        store.restore("blob", 1).unwrap();
+        assert!(store.is_binary("blob"));
+        assert_eq!(store.checked_get_bytes("blob").unwrap(), bytes);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn get_many_keeps_request_order_and_skips_missing() {
+        let mut store = SnippetStore::default();
+        store.insert("a", "1");
+        store.insert("b", "2");
+
+        let found = store.get_many(&["b", "missing", "a"]);
+        let found: Vec<(&str, &str)> = found
+            .iter()
+            .map(|snippet| (snippet.name.as_str(), snippet.code.as_str()))
+            .collect();
+        assert_eq!(found, [("b", "2"), ("a", "1")]);
+    }
+
+    #[test]
+    fn exists_tracks_inserts_and_removals() {
+        let mut store = SnippetStore::default();
+        assert!(!store.exists("a"));
+        store.insert("a", "code");
+        assert!(store.exists("a"));
+        store.remove("a");
+        assert!(!store.exists("a"));
+    }
+
+    #[test]
+    fn unique_name_adds_a_suffix_on_collision() {
+        let mut store = SnippetStore::default();
+        assert_eq!(store.unique_name("note"), "note");
+        store.insert("note", "");
+        store.insert("note-2", "");
+        assert_eq!(store.unique_name("note"), "note-3");
+    }
+
+    #[test]
+    fn rename_moves_code_to_new_name() {
+        let mut store = SnippetStore::default();
+        store.insert("tpyo", "code");
+        store.rename("tpyo", "typo").unwrap();
+        assert_eq!(store.get("typo"), Some("code"));
+        assert!(!store.exists("tpyo"));
+    }
+
+    #[test]
+    fn rename_refuses_to_clobber() {
+        let mut store = SnippetStore::default();
+        store.insert("a", "1");
+        store.insert("b", "2");
+
+        let err = store.rename("a", "b").unwrap_err();
+        assert!(matches!(err, SnippetError::AlreadyExists(name) if name == "b"));
+        assert_eq!(store.get("a"), Some("1"));
+        assert_eq!(store.get("b"), Some("2"));
+    }
+
+    #[test]
+    fn rename_of_missing_snippet_fails() {
+        let mut store = SnippetStore::default();
+        let err = store.rename("missing", "new").unwrap_err();
+        assert!(matches!(err, SnippetError::NotFound(name) if name == "missing"));
+        assert!(!store.exists("new"));
+    }
+
+    #[test]
+    fn delete_many_counts_only_removed_names() {
+        let mut store = SnippetStore::default();
+        store.insert("tmp-1", "");
+        store.insert("tmp-2", "");
+        store.insert("keep", "");
+
+        assert_eq!(store.delete_many(&["tmp-1", "tmp-2", "missing"]), 2);
+        assert_eq!(store.names().collect::<Vec<_>>(), ["keep"]);
+    }
+
+    #[test]
+    fn delete_prefix_removes_only_matching_names() {
+        let mut store = SnippetStore::default();
+        store.insert("tmp-1", "");
+        store.insert("tmp-2", "");
+        store.insert("keep-tmp-3", "");
+        store.insert("tmp", "");
+
+        assert_eq!(store.delete_prefix("tmp-"), 2);
+        let mut left: Vec<&str> = store.names().collect();
+        left.sort_unstable();
+        assert_eq!(left, ["keep-tmp-3", "tmp"]);
+    }
+
+    #[test]
+    fn clear_empties_the_store() {
+        let mut store = SnippetStore::default();
+        store.insert("a", "1");
+        store.insert("b", "2");
+
+        assert_eq!(store.clear(), 2);
+        assert!(store.is_empty());
+        assert_eq!(store.clear(), 0);
+    }
+
+    #[test]
+    fn stats_aggregate_sizes_and_languages() {
+        let snippets = [
+            snippet("a", "fn a() {}", Some("rust")),
+            snippet("b", "fn b() {}", Some("rust")),
+            snippet("c", "SELECT 1;", Some("sql")),
+            snippet("d", "echo d", None),
+        ];
+
+        let stats = stats(&snippets);
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.total_bytes, 33);
+        assert_eq!(stats.average_bytes, 8.25);
+        assert_eq!(
+            stats.per_language,
+            BTreeMap::from([("rust".to_string(), 2), ("sql".to_string(), 1)])
+        );
+        assert_eq!(stats.without_language, 1);
+        assert_eq!((stats.oldest, stats.newest), (None, None));
+    }
+
+    #[test]
+    fn stats_find_the_oldest_and_newest_snippets() {
+        let mut snippets = [
+            snippet("legacy", "x", None),
+            snippet("middle", "x", None),
+            snippet("newest", "x", None),
+            snippet("oldest", "x", None),
+        ];
+        for (snippet, at) in snippets[1..].iter_mut().zip([20, 30, 10]) {
+            snippet.created_at = Some(at);
+        }
+
+        let stats = stats(&snippets);
+        assert_eq!(stats.oldest, Some(("oldest".to_string(), 10)));
+        assert_eq!(stats.newest, Some(("newest".to_string(), 30)));
+    }
+
+    #[test]
+    fn stats_of_empty_store_are_zero() {
+        assert_eq!(stats(&[]), Stats::default());
+    }
+
+    #[test]
+    fn overwriting_keeps_a_bounded_history() {
+        let mut store = SnippetStore::default();
+        store.set_history_depth(2);
+        store.insert("v", "one");
+        store.insert("v", "two");
+
+        let history = store.history("v").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].code, "one");
+
+        store.insert("v", "three");
+        store.insert("v", "four");
+        let codes: Vec<String> = store
+            .history("v")
+            .unwrap()
+            .into_iter()
+            .map(|version| version.code)
+            .collect();
+        assert_eq!(codes, ["three", "two"]);
+    }
+
+    #[test]
+    fn import_overwrite_keeps_history_and_metadata() {
+        let mut store = SnippetStore::default();
+        store.set_history_depth(5);
+        store.insert("a", "v1");
+        store.insert("a", "v2");
+        store.set_language("a", Some("rust".into()));
+        store.touch("a").unwrap();
+
+        let incoming = vec![snippet("a", "v3", None)];
+        store.import(incoming, ImportMode::Overwrite, DEFAULT_MAX_BYTES);
+
+        assert_eq!(store.get("a"), Some("v3"));
+        assert_eq!(store.language("a"), Some("rust"));
+        assert!(store.last_accessed("a").is_some());
+        let codes: Vec<String> = store
+            .history("a")
+            .unwrap()
+            .into_iter()
+            .map(|version| version.code)
+            .collect();
+        assert_eq!(codes, ["v2", "v1"]);
+    }
+
+    #[test]
+    fn history_is_off_by_default() {
+        let mut store = SnippetStore::default();
+        store.insert("v", "one");
+        store.insert("v", "two");
+        assert_eq!(store.history("v"), Some(Vec::new()));
+        assert_eq!(store.history("missing"), None);
+    }
+
+    #[test]
+    fn restore_brings_back_an_earlier_version() {
+        let mut store = SnippetStore::default();
+        store.set_history_depth(5);
+        store.insert("v", "one");
+        store.insert("v", "two");
+
+        store.restore("v", 1).unwrap();
+        assert_eq!(store.get("v"), Some("one"));
+        assert!(store.checked_get("v").is_ok());
+        assert_eq!(store.history("v").unwrap()[0].code, "two");
+
+        assert!(matches!(
+            store.restore("v", 2),
+            Err(SnippetError::NoSuchVersion(name, 2)) if name == "v"
+        ));
+        assert!(matches!(
+            store.restore("v", 0),
+            Err(SnippetError::NoSuchVersion(_, 0))
+        ));
+    }
+
+    #[test]
+    fn restore_keeps_the_replaced_body_without_a_history_depth() {
+        let mut store = SnippetStore::default();
+        store.set_history_depth(3);
+        store.insert("a", "v1");
+        store.insert("a", "v2");
+        store.set_history_depth(0);
+
+        store.restore("a", 1).unwrap();
+        assert_eq!(store.get("a"), Some("v1"));
+        let history = store.history("a").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].code, "v2");
+    }
+
+    #[test]
+    fn versions_record_when_their_body_was_saved() {
+        let mut store = SnippetStore::default();
+        store.set_history_depth(3);
+        store.insert("a", "v1");
+        store.snippets.get_mut("a").unwrap().updated_at = Some(42);
+
+        store.insert("a", "v2");
+        assert_eq!(store.history("a").unwrap()[0].saved_at, 42);
+    }
+
+    #[test]
+    fn confirm_defaults_to_no() {
+        let mut prompt = Vec::new();
+        assert!(!confirm(io::Cursor::new("\n"), &mut prompt, "Delete?").unwrap());
+        assert!(!confirm(io::Cursor::new(""), io::sink(), "Delete?").unwrap());
+        assert!(!confirm(io::Cursor::new("nope\n"), io::sink(), "Delete?").unwrap());
+        assert_eq!(String::from_utf8(prompt).unwrap(), "Delete? [y/N] ");
+    }
+
+    #[test]
+    fn confirm_accepts_yes() {
+        assert!(confirm(io::Cursor::new("y\n"), io::sink(), "Delete?").unwrap());
+        assert!(confirm(io::Cursor::new(" yes \n"), io::sink(), "Delete?").unwrap());
+    }
+
+    #[test]
+    fn number_lines_handles_empty_and_single_line_bodies() {
+        assert_eq!(number_lines(""), "");
+        assert_eq!(number_lines("fn main() {}\n"), "1 | fn main() {}\n");
+    }
+
+    #[test]
+    fn number_lines_pads_to_the_widest_number() {
+        let body: String = (1..=100).map(|n| format!("line {n}\n")).collect();
+        let numbered = number_lines(&body);
+        let lines: Vec<&str> = numbered.lines().collect();
+
+        assert_eq!(lines.len(), 100);
+        assert_eq!(lines[0], "  1 | line 1");
+        assert_eq!(lines[9], " 10 | line 10");
+        assert_eq!(lines[99], "100 | line 100");
+    }
+
+    #[test]
+    fn name_from_code_uses_first_non_empty_line() {
+        assert_eq!(
+            name_from_code("\n   \n  fn main() {}  \nmore"),
+            Some("fn main() {}".to_string())
+        );
+        assert_eq!(name_from_code(" \n\t\n"), None);
+    }
+
+    #[test]
+    fn name_from_code_truncates_long_lines() {
+        let name = name_from_code(&"é".repeat(100)).unwrap();
+        assert_eq!(name.chars().count(), MAX_AUTO_NAME_CHARS);
+    }
+
+    #[test]
+    fn append_creates_missing_snippet() {
+        let mut store = SnippetStore::default();
+        store.append("log", "first\n").unwrap();
+        assert_eq!(store.get("log"), Some("first\n"));
+    }
+
+    #[test]
+    fn append_adds_a_line_to_existing_snippet() {
+        let mut store = SnippetStore::default();
+        store.insert("a", "first");
+        store.insert("b", "first\n");
+        store.append("a", "second\n").unwrap();
+        store.append("b", "second\n").unwrap();
+        assert_eq!(store.get("a"), Some("first\nsecond\n"));
+        assert_eq!(store.get("b"), Some("first\nsecond\n"));
+    }
+
+    #[test]
+    fn read_code_accepts_input_at_the_limit() {
+        let code = read_code(io::Cursor::new("12345"), 5).unwrap();
+        assert_eq!(code, "12345");
+    }
+
+    #[test]
+    fn read_code_reports_the_limit_it_exceeded() {
+        let err = read_code(io::Cursor::new("1234567890"), 5).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "snippet is more than 5 bytes, which exceeds the limit"
+        );
+    }
+
+    #[test]
+    fn read_code_reports_the_limit_when_the_cut_splits_a_character() {
+        let err = read_code(io::Cursor::new("ééé"), 3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "snippet is more than 3 bytes, which exceeds the limit"
+        );
+    }
+
+    #[test]
+    fn read_code_stops_at_the_limit_on_endless_input() {
+        let err = read_code(io::repeat(b'y'), 10).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn bounded_lines_skip_only_the_overlong_line() {
+        let mut input = io::Cursor::new("short\r\nthis line is too long\nok\nlast");
+        let mut lines = Vec::new();
+        while let Some(line) = read_bounded_line(&mut input, 5).unwrap() {
+            lines.push(line.map_err(|err| err.to_string()));
+        }
+
+        assert_eq!(
+            lines,
+            [
+                Ok(b"short".to_vec()),
+                Err("line is more than 5 bytes, which exceeds the limit".to_string()),
+                Ok(b"ok".to_vec()),
+                Ok(b"last".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_code_accepts_the_largest_limit() {
+        let code = read_code(io::Cursor::new("fn main() {}"), u64::MAX).unwrap();
+        assert_eq!(code, "fn main() {}");
+    }
+
+    #[test]
+    fn read_code_rejects_invalid_utf8() {
+        let err = read_code(io::Cursor::new(vec![0xff, 0xfe]), 10).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn corrupted_file_fails_without_recovery() {
+        let path = temp_path("corrupted");
+        fs::write(&path, "{ not json").unwrap();
+
+        let err = SnippetStore::load_with_recovery(&path, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn corrupted_file_is_moved_aside_with_recovery() {
+        let dir = std::env::temp_dir().join(format!("snippets-app-recover-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("snippets.json");
+        fs::write(&path, "{ not json").unwrap();
+
+        let store = SnippetStore::load_with_recovery(&path, true).unwrap();
+        assert!(store.is_empty());
+        assert!(!path.exists());
+
+        let backups: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(backups.len(), 1);
+        let backup = backups[0].as_ref().unwrap().path();
+        assert!(backup.to_string_lossy().contains("snippets.json.corrupt-"));
+        let recovery = store.recovery().unwrap();
+        assert_eq!(recovery.backup, backup);
+        assert!(recovery.error.contains("line 1"), "{}", recovery.error);
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "{ not json");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_file_loads_empty_store() {
+        let store = SnippetStore::load(&temp_path("missing")).unwrap();
+        assert_eq!(store.get("anything"), None);
+        assert!(store.recovery().is_none());
+    }
+}
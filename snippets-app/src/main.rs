@@ -1,87 +1,1205 @@
-use serde::{Deserialize, Serialize};
+use snippets_app::{
+    clipboard, confirm, date, diff, edit, expand, fuzzy, glob, history_depth, matching_line,
+    max_snippet_bytes, name_from_code, number_lines, read_bounded_line, read_bytes, read_code,
+    read_import, read_only_error, read_only_mode, stats, storage_path, table, template, too_large,
+    unix_now, ImportMode, Snippet, SnippetError, SnippetStore, StoreLock,
+};
 use std::{
     collections::HashMap,
     env,
-    fs,
-    io::{self, Read},
-    path::PathBuf,
+    fmt::Write as _,
+    fs::{self, OpenOptions},
+    io::{self, BufRead, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::{self, Command},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-#[derive(Debug, Serialize, Deserialize, Default)]
-struct SnippetStore {
-    snippets: HashMap<String, String>,
+const NOT_FOUND_EXIT_CODE: i32 = 3;
+const PICK_CANDIDATES: usize = 10;
+// Room for the name, quotes and other fields around a --batch body.
+const BATCH_LINE_OVERHEAD: u64 = 64 * 1024;
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
+}
+
+fn utf8(bytes: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+// The library only records a recovery; telling the user is up to us.
+fn warn_if_recovered(store: &SnippetStore, path: &Path) {
+    if let Some(recovery) = store.recovery() {
+        eprintln!(
+            "warning: {} is corrupted ({}); moved it to {} and started empty",
+            path.display(),
+            recovery.error,
+            recovery.backup.display()
+        );
+    }
+}
+
+fn load(path: &Path) -> io::Result<SnippetStore> {
+    let store = SnippetStore::load(path)?;
+    warn_if_recovered(&store, path);
+    Ok(store)
+}
+
+fn load_shared(path: &Path) -> io::Result<SnippetStore> {
+    let store = SnippetStore::load_shared(path)?;
+    warn_if_recovered(&store, path);
+    Ok(store)
+}
+
+fn lock_for_writing(path: &Path) -> io::Result<StoreLock> {
+    if read_only_mode() {
+        return Err(read_only_error());
+    }
+    history_depth()?;
+    StoreLock::exclusive(path)
+}
+
+// A dry run only reads, so it takes no exclusive lock and creates no lock
+// file, never moves a corrupted store aside, and works under read-only
+// mode: the store it returns is never saved. It still checks the history
+// depth so it fails wherever the real command would.
+fn load_preview(path: &Path) -> io::Result<SnippetStore> {
+    let depth = history_depth()?;
+    let _lock = StoreLock::shared_existing(path);
+    let mut store = SnippetStore::load_with_recovery(path, false)?;
+    store.set_history_depth(depth);
+    Ok(store)
+}
+
+fn load_for_writing(path: &Path, dry_run: bool) -> io::Result<(Option<StoreLock>, SnippetStore)> {
+    if dry_run {
+        return Ok((None, load_preview(path)?));
+    }
+    let lock = lock_for_writing(path)?;
+    Ok((Some(lock), load(path)?))
+}
+
+fn probe_directory(path: &Path) -> io::Result<()> {
+    let mut probe = path.as_os_str().to_owned();
+    probe.push(format!(".doctor-{}", process::id()));
+    let probe = PathBuf::from(probe);
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)?;
+    fs::remove_file(probe)
 }
 
-impl SnippetStore {
-    fn load(path: &PathBuf) -> Self {
-        if let Ok(content) = fs::read_to_string(path) {
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            SnippetStore::default()
+fn doctor(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Storage: json");
+    println!("Path: {}", path.display());
+
+    // Saves write a sibling temp file and rename it over the store, so the
+    // directory has to be writable even when the file itself is.
+    match probe_directory(path) {
+        Ok(()) => println!("Directory writable: yes"),
+        Err(err) => println!("Directory writable: no ({err})"),
+    }
+
+    if !path.exists() {
+        println!("File: not created yet (it is written on the first save)");
+        return Ok(());
+    }
+
+    let _lock = StoreLock::shared(path);
+    let writable = OpenOptions::new().append(true).open(path).is_ok();
+    println!("Writable: {}", if writable { "yes" } else { "no" });
+
+    match fs::read_to_string(path) {
+        Ok(content) => {
+            println!("Readable: yes");
+            match serde_json::from_str::<SnippetStore>(&content) {
+                Ok(store) => println!("JSON: valid, {} snippet(s)", store.len()),
+                Err(err) => println!("JSON: corrupted ({err})"),
+            }
         }
+        Err(err) => println!("Readable: no ({err})"),
+    }
+    Ok(())
+}
+
+fn open_in_viewer(path: &Path) -> io::Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = Command::new("xdg-open");
+
+    let status = command.arg(path).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("viewer exited with {status}")))
     }
+}
 
-    fn save(&self, path: &PathBuf) -> io::Result<()> {
-        let data = serde_json::to_string_pretty(self).unwrap();
-        fs::write(path, data)
+fn terminal_width() -> usize {
+    const FALLBACK_WIDTH: usize = 80;
+    if !io::stdout().is_terminal() {
+        return FALLBACK_WIDTH;
     }
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(FALLBACK_WIDTH)
 }
 
-fn storage_path() -> PathBuf {
-    PathBuf::from("snippets.json")
+// `since` is inclusive and `until` exclusive, so consecutive ranges never
+// share a snippet. Without a creation time a snippet matches no range.
+fn created_between(created_at: Option<u64>, since: Option<u64>, until: Option<u64>) -> bool {
+    if since.is_none() && until.is_none() {
+        return true;
+    }
+    created_at.is_some_and(|at| {
+        since.is_none_or(|since| at >= since) && until.is_none_or(|until| at < until)
+    })
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut args = env::args().skip(1);
+fn main() {
+    if let Err(err) = run() {
+        match err.downcast_ref::<SnippetError>() {
+            Some(SnippetError::NotFound(name)) => {
+                eprintln!("Snippet '{name}' not found.");
+                process::exit(NOT_FOUND_EXIT_CODE);
+            }
+            _ => {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let dry_run = take_flag(&mut args, "--dry-run");
+    let json = take_flag(&mut args, "--json");
+    let mut args = args.into_iter();
 
     let action = args.next().unwrap_or_default();
     let name = args.next();
 
     match action.as_str() {
-        "--name" => {
-            let name = name.expect("snippet name is required after --name");
-            let mut buffer = String::new();
-            io::stdin().read_to_string(&mut buffer)?;
+        "--name" | "--append" => {
+            let name = name.unwrap_or_else(|| panic!("snippet name is required after {action}"));
+            let mut copy_from = None;
+            let mut force = false;
+            let mut append = action == "--append";
+            let mut language = None;
+            let mut file = None;
+            let mut metadata = HashMap::new();
+            let mut binary = false;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--lang" => {
+                        language = Some(args.next().expect("a language is required after --lang"));
+                    }
+                    "--copy-from" => {
+                        copy_from = Some(
+                            args.next()
+                                .expect("snippet name is required after --copy-from"),
+                        );
+                    }
+                    "--file" => {
+                        file = Some(args.next().expect("a file path is required after --file"));
+                    }
+                    "--meta" => {
+                        let pair = args.next().expect("key=value is required after --meta");
+                        let (key, value) =
+                            pair.split_once('=').ok_or("--meta expects key=value")?;
+                        metadata.insert(key.to_string(), value.to_string());
+                    }
+                    "--force" => force = true,
+                    "--append" => append = true,
+                    "--binary" => binary = true,
+                    other => return Err(format!("unknown option '{other}'").into()),
+                }
+            }
+            if file.is_some() && copy_from.is_some() {
+                return Err("use only one of --file and --copy-from".into());
+            }
+
+            if binary && append {
+                return Err("--binary can't be combined with --append".into());
+            }
+
+            let max_bytes = max_snippet_bytes()?;
+            // Text is checked as it's read; only --binary takes arbitrary bytes.
+            let read = |reader: Box<dyn Read>| {
+                if binary {
+                    read_bytes(reader, max_bytes)
+                } else {
+                    read_code(reader, max_bytes).map(String::into_bytes)
+                }
+            };
+            let mut body = Vec::new();
+            if let Some(file) = &file {
+                let source = fs::File::open(file)
+                    .map_err(|err| format!("could not open '{file}': {err}"))?;
+                body = read(Box::new(source))?;
+            } else if copy_from.is_none() {
+                body = read(Box::new(io::stdin()))?;
+            }
 
             let path = storage_path();
-            let mut store = SnippetStore::load(&path);
-            store.snippets.insert(name.clone(), buffer);
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+
+            if let Some(source) = copy_from {
+                if store.exists(&name) && !force && !append {
+                    return Err(format!(
+                        "snippet '{name}' already exists (use --force to overwrite)"
+                    )
+                    .into());
+                }
+                body = store.checked_get_bytes(&source)?;
+                binary |= store.is_binary(&source);
+                if binary && append {
+                    return Err(SnippetError::Binary(source).into());
+                }
+                if language.is_none() {
+                    language = store.language(&source).map(String::from);
+                }
+            }
+
+            let same_language = language.is_none() || store.language(&name) == language.as_deref();
+            let same_metadata = store.metadata(&name).is_some_and(|known| {
+                metadata
+                    .iter()
+                    .all(|(key, value)| known.get(key) == Some(value))
+            });
+            if !append
+                && same_language
+                && (metadata.is_empty() || same_metadata)
+                && store.is_binary(&name) == binary
+                && store.checked_get_bytes(&name).ok().as_deref() == Some(body.as_slice())
+            {
+                println!("Snippet '{name}' unchanged.");
+                return Ok(());
+            }
+
+            if dry_run {
+                let verb = if append { "append to" } else { "save" };
+                println!("[dry-run] Would {verb} snippet '{name}'.");
+                return Ok(());
+            }
+
+            if append {
+                store.append(&name, &utf8(body)?)?;
+            } else if binary {
+                store.insert_bytes(name.clone(), &body);
+            } else {
+                store.insert(name.clone(), utf8(body)?);
+            }
+            if language.is_some() {
+                store.set_language(&name, language);
+            }
+            store.add_metadata(&name, metadata);
             store.save(&path)?;
 
             println!("Snippet '{name}' saved.");
         }
-        "--read" => {
-            let name = name.expect("snippet name is required after --read");
+        "--read" | "--last" => {
+            let last = action == "--last";
+            let mut args = name.into_iter().chain(args);
+            let mut names = Vec::new();
+            if !last {
+                names.push(args.next().expect("snippet name is required after --read"));
+            }
+            let mut values = HashMap::new();
+            let mut allow_unfilled = false;
+            let mut expand_env = false;
+            let mut strict_env = false;
+            let mut to_clipboard = false;
+            let mut numbers = false;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--read" => {
+                        names.push(args.next().expect("snippet name is required after --read"));
+                    }
+                    "--set" => {
+                        let pair = args.next().expect("key=value is required after --set");
+                        let (key, value) = pair.split_once('=').ok_or("--set expects key=value")?;
+                        values.insert(key.to_string(), value.to_string());
+                    }
+                    "--allow-unfilled" => allow_unfilled = true,
+                    "--expand-env" => expand_env = true,
+                    "--strict-env" => strict_env = true,
+                    "--clipboard" => to_clipboard = true,
+                    "--numbers" => numbers = true,
+                    other if !other.starts_with("--") => names.push(arg),
+                    other => return Err(format!("unknown option '{other}'").into()),
+                }
+            }
+
+            if last && !names.is_empty() {
+                return Err("--last reads one snippet and takes no names".into());
+            }
+            let store = load_shared(&storage_path())?;
+            if last {
+                names.push(
+                    store
+                        .latest()
+                        .ok_or("no snippets with a creation time yet")?
+                        .name,
+                );
+            }
+            let mut missing = false;
+            let mut found = Vec::new();
+            let mut out = String::new();
+
+            for name in &names {
+                let code = match store.checked_get(name) {
+                    Ok(code) => code,
+                    Err(SnippetError::NotFound(_)) => {
+                        eprintln!("Snippet '{name}' not found.");
+                        missing = true;
+                        continue;
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+                if store.is_binary(name) {
+                    // Raw bytes go out untouched, so none of the text options apply.
+                    let as_text = !values.is_empty()
+                        || allow_unfilled
+                        || expand_env
+                        || strict_env
+                        || numbers
+                        || to_clipboard;
+                    if as_text || (!json && names.len() > 1) {
+                        return Err(SnippetError::Binary(name.clone()).into());
+                    }
+                    if !json {
+                        io::stdout().write_all(&store.checked_get_bytes(name)?)?;
+                        return Ok(());
+                    }
+                }
+                let mut code = code.to_string();
+                if !values.is_empty() || allow_unfilled {
+                    code = template::render(&code, &values, allow_unfilled)?;
+                }
+                if expand_env || strict_env {
+                    code = expand::expand_env(&code, |var| env::var(var).ok(), strict_env)?;
+                }
+
+                if json {
+                    if let Some(snippet) = store.snippet(name) {
+                        found.push(Snippet { code, ..snippet });
+                    }
+                    continue;
+                }
+                if names.len() > 1 {
+                    match store.language(name) {
+                        Some(language) => writeln!(out, "===== {name} ({language}) =====")?,
+                        None => writeln!(out, "===== {name} =====")?,
+                    }
+                }
+                if numbers {
+                    write!(out, "{}", number_lines(&code))?;
+                } else {
+                    writeln!(out, "{code}")?;
+                }
+            }
+
+            if json {
+                if names.len() > 1 {
+                    writeln!(out, "{}", serde_json::to_string_pretty(&found)?)?;
+                } else if let Some(snippet) = found.first() {
+                    writeln!(out, "{}", serde_json::to_string_pretty(snippet)?)?;
+                }
+            }
+            if to_clipboard && !out.is_empty() {
+                match clipboard::copy(out.strip_suffix('\n').unwrap_or(&out)) {
+                    Ok(()) => println!("Copied to the clipboard."),
+                    Err(err) => {
+                        eprintln!(
+                            "warning: could not copy to the clipboard ({err}); printing instead."
+                        );
+                        print!("{out}");
+                    }
+                }
+            } else {
+                print!("{out}");
+            }
+            if missing {
+                process::exit(NOT_FOUND_EXIT_CODE);
+            }
+        }
+        "--list" => {
+            let mut language = None;
+            let mut filters = Vec::new();
+            let mut offset = 0;
+            let mut limit = usize::MAX;
+            let mut as_table = false;
+            let mut since = None;
+            let mut until = None;
+            let mut options = name.into_iter().chain(args.by_ref());
+            while let Some(arg) = options.next() {
+                match arg.as_str() {
+                    "--lang" => {
+                        language =
+                            Some(options.next().expect("a language is required after --lang"));
+                    }
+                    "--filter-meta" => {
+                        let pair = options
+                            .next()
+                            .expect("key=value is required after --filter-meta");
+                        let (key, value) = pair
+                            .split_once('=')
+                            .ok_or("--filter-meta expects key=value")?;
+                        filters.push((key.to_string(), value.to_string()));
+                    }
+                    "--since" => {
+                        let text = options.next().expect("a date is required after --since");
+                        since = Some(date::parse(&text)?);
+                    }
+                    "--until" => {
+                        let text = options.next().expect("a date is required after --until");
+                        until = Some(date::parse(&text)?);
+                    }
+                    "--table" => as_table = true,
+                    "--offset" => {
+                        offset = options
+                            .next()
+                            .expect("a number is required after --offset")
+                            .parse()?;
+                    }
+                    "--limit" => {
+                        limit = options
+                            .next()
+                            .expect("a number is required after --limit")
+                            .parse()?;
+                    }
+                    other => return Err(format!("unknown option '{other}'").into()),
+                }
+            }
+
+            let store = load_shared(&storage_path())?;
+            let matching = store.list().into_iter().filter(|snippet| {
+                language.as_deref().is_none_or(|wanted| {
+                    snippet
+                        .language
+                        .as_deref()
+                        .is_some_and(|language| language.eq_ignore_ascii_case(wanted))
+                }) && filters
+                    .iter()
+                    .all(|(key, value)| snippet.metadata.get(key) == Some(value))
+                    && created_between(snippet.created_at, since, until)
+            });
+            let page: Vec<Snippet> = matching.skip(offset).take(limit).collect();
+            if json {
+                for snippet in &page {
+                    store.checked_get(&snippet.name)?;
+                }
+                println!("{}", serde_json::to_string_pretty(&page)?);
+                return Ok(());
+            }
+            if as_table {
+                print!("{}", table::format_table(&page, terminal_width()));
+                return Ok(());
+            }
+            for snippet in page {
+                let created = snippet
+                    .created_at
+                    .map_or("unknown".to_string(), date::format);
+                println!("{}\t{created}", snippet.name);
+            }
+        }
+        "--export" => {
+            let target = name.expect("a file path is required after --export");
+            let store = load_shared(&storage_path())?;
+            let exported = store.export(Path::new(&target))?;
+
+            println!("{exported} snippet(s) exported to '{target}'.");
+        }
+        "--import" => {
+            let source = name.expect("a file path is required after --import");
+            let mode = match args.next().as_deref() {
+                Some("--import-mode") => args
+                    .next()
+                    .expect("skip, overwrite or rename is required after --import-mode")
+                    .parse()?,
+                Some(other) => return Err(format!("unknown option '{other}'").into()),
+                None => ImportMode::Skip,
+            };
+            let snippets = read_import(Path::new(&source))?;
+            let max_bytes = max_snippet_bytes()?;
+
             let path = storage_path();
-            let store = SnippetStore::load(&path);
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            let summary = store.import(snippets, mode, max_bytes);
+
+            let report = format!(
+                "{} imported, {} renamed, {} skipped, {} too large.",
+                summary.imported, summary.renamed, summary.skipped, summary.too_large
+            );
+            if dry_run {
+                println!("[dry-run] Would have: {report}");
+                return Ok(());
+            }
+            store.save(&path)?;
+
+            println!("{report}");
+        }
+        "--count" => {
+            let store = load_shared(&storage_path())?;
+            println!("{}", store.len());
+        }
+        "--stats" => {
+            let store = load_shared(&storage_path())?;
+            let stats = stats(&store.list());
 
-            if let Some(code) = store.snippets.get(&name) {
+            println!("Snippets:      {}", stats.total);
+            println!("Total bytes:   {}", stats.total_bytes);
+            println!("Average bytes: {:.1}", stats.average_bytes);
+            if let (Some((oldest, first)), Some((newest, last))) = (&stats.oldest, &stats.newest) {
+                println!("Oldest:        {oldest} ({})", date::format(*first));
+                println!("Newest:        {newest} ({})", date::format(*last));
+            }
+            if !stats.per_language.is_empty() || stats.without_language > 0 {
+                println!("By language:");
+                for (language, count) in &stats.per_language {
+                    println!("  {language}: {count}");
+                }
+                if stats.without_language > 0 {
+                    println!("  (none): {}", stats.without_language);
+                }
+            }
+        }
+        "--search" => {
+            let query = name.expect("a query is required after --search");
+            let store = load_shared(&storage_path())?;
+
+            for snippet in store.search(&query) {
+                let code = store.checked_get(&snippet.name)?;
+                let preview = matching_line(code, &query)
+                    .or_else(|| code.lines().next().map(str::trim))
+                    .unwrap_or_default();
+                println!("{}: {preview}", snippet.name);
+            }
+        }
+        "--pick" => {
+            if !io::stdout().is_terminal() {
+                return Err("--pick needs an interactive terminal; use --search instead".into());
+            }
+            let store = load_shared(&storage_path())?;
+            let names: Vec<&str> = store.names().collect();
+            let mut query = name.unwrap_or_default();
+            let mut input = io::stdin().lock();
+
+            let chosen = loop {
+                let ranked = fuzzy::rank(&query, &names);
+                if ranked.is_empty() {
+                    println!("No snippets match '{query}'.");
+                }
+                for (i, name) in ranked.iter().take(PICK_CANDIDATES).enumerate() {
+                    println!("{:>2}) {name}", i + 1);
+                }
+                print!("Pick a number or type a new filter (empty to quit): ");
+                io::stdout().flush()?;
+
+                let mut line = String::new();
+                input.read_line(&mut line)?;
+                let line = line.trim();
+                if line.is_empty() {
+                    println!("Aborted.");
+                    return Ok(());
+                }
+                match line.parse::<usize>() {
+                    Ok(n) if (1..=ranked.len().min(PICK_CANDIDATES)).contains(&n) => {
+                        break ranked[n - 1].to_string();
+                    }
+                    _ => query = line.to_string(),
+                }
+            };
+
+            println!("{}", store.checked_get(&chosen)?);
+        }
+        "--open" => {
+            let name = name.expect("snippet name is required after --open");
+            let store = load_shared(&storage_path())?;
+
+            let code = store.checked_get(&name)?;
+            let extension = edit::extension_for(store.language(&name));
+            let file = edit::temp_file("snippets-app-", &name, extension, code)?;
+
+            // A viewer that started may still be reading the file, so it is
+            // only cleaned up when nothing opened it.
+            if let Err(err) = open_in_viewer(&file) {
+                fs::remove_file(&file).ok();
+                eprintln!("warning: could not open a viewer ({err}); printing instead.");
                 println!("{code}");
+            }
+        }
+        "--edit" => {
+            let name = name.expect("snippet name is required after --edit");
+            if read_only_mode() && !dry_run {
+                return Err(read_only_error().into());
+            }
+            let path = storage_path();
+            let store = if dry_run {
+                load_preview(&path)?
             } else {
-                eprintln!("Snippet '{name}' not found.");
+                load_shared(&path)?
+            };
+            if store.is_binary(&name) {
+                return Err(SnippetError::Binary(name).into());
             }
+            let initial = match store.checked_get(&name) {
+                Ok(code) => code.to_string(),
+                Err(SnippetError::NotFound(_)) => String::new(),
+                Err(err) => return Err(err.into()),
+            };
+
+            let extension = edit::extension_for(store.language(&name));
+            let Some(code) = edit::edit_text(&edit::editor(), &name, extension, &initial)? else {
+                println!("Snippet '{name}' unchanged.");
+                return Ok(());
+            };
+            let max_bytes = max_snippet_bytes()?;
+            if code.len() as u64 > max_bytes {
+                return Err(too_large(code.len() as u64, max_bytes).into());
+            }
+            if dry_run {
+                println!("[dry-run] Would save edited snippet '{name}'.");
+                return Ok(());
+            }
+
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            store.insert(name.clone(), code);
+            store.save(&path)?;
+
+            println!("Snippet '{name}' saved.");
         }
         "--delete" => {
             let name = name.expect("snippet name is required after --delete");
+            let yes = match args.next().as_deref() {
+                Some("--yes" | "-y") => true,
+                Some(other) => return Err(format!("unknown option '{other}'").into()),
+                None => false,
+            };
             let path = storage_path();
-            let mut store = SnippetStore::load(&path);
 
-            if store.snippets.remove(&name).is_some() {
+            // Ask before locking so a pending prompt doesn't block readers.
+            if !yes && !dry_run && io::stdin().is_terminal() {
+                let store = load_shared(&path)?;
+                if let Some(code) = store.get(&name) {
+                    let first_line = code.lines().next().unwrap_or_default();
+                    let prompt = format!("Delete '{name}' ({first_line})?");
+                    if !confirm(io::stdin().lock(), io::stdout(), &prompt)? {
+                        println!("Aborted.");
+                        return Ok(());
+                    }
+                }
+            }
+
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            if dry_run && store.exists(&name) {
+                println!("[dry-run] Would delete snippet '{name}'.");
+            } else if store.remove(&name).is_some() {
                 store.save(&path)?;
                 println!("Snippet '{name}' deleted.");
             } else {
-                eprintln!("Snippet '{name}' not found.");
+                return Err(SnippetError::NotFound(name).into());
+            }
+        }
+        "--rename" => {
+            let old = name.expect("old snippet name is required after --rename");
+            let new = args
+                .next()
+                .expect("new snippet name is required after --rename OLD");
+
+            let path = storage_path();
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            store.rename(&old, &new)?;
+
+            if dry_run {
+                println!("[dry-run] Would rename snippet '{old}' to '{new}'.");
+                return Ok(());
+            }
+            store.save(&path)?;
+
+            println!("Snippet '{old}' renamed to '{new}'.");
+        }
+        "--history" => {
+            let name = name.expect("snippet name is required after --history");
+            let store = load_shared(&storage_path())?;
+            let history = store
+                .history(&name)
+                .ok_or_else(|| SnippetError::NotFound(name.clone()))?;
+
+            let known = |at: Option<u64>| at.map_or("unknown".to_string(), |at| at.to_string());
+            println!(
+                "Created at {}, updated at {}.",
+                known(store.created_at(&name)),
+                known(store.updated_at(&name))
+            );
+            if history.is_empty() {
+                println!("Snippet '{name}' has no earlier versions.");
+            }
+            for (i, version) in history.iter().enumerate() {
+                let first_line = version.code.lines().next().unwrap_or_default();
+                println!("{:>2}) saved at {}  {first_line}", i + 1, version.saved_at);
+            }
+        }
+        "--restore" => {
+            let name = name.expect("snippet name is required after --restore");
+            let version = match args.next().as_deref() {
+                Some("--version") => args
+                    .next()
+                    .expect("a version number is required after --version")
+                    .parse()?,
+                Some(other) => return Err(format!("unknown option '{other}'").into()),
+                None => 1,
+            };
+
+            let path = storage_path();
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            store.restore(&name, version)?;
+
+            if dry_run {
+                println!("[dry-run] Would restore version {version} of snippet '{name}'.");
+                return Ok(());
+            }
+            store.save(&path)?;
+
+            println!("Snippet '{name}' restored to version {version}.");
+        }
+        "--touch" => {
+            let name = name.expect("snippet name is required after --touch");
+
+            let path = storage_path();
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            store.touch(&name)?;
+
+            if dry_run {
+                println!("[dry-run] Would mark snippet '{name}' as accessed.");
+                return Ok(());
+            }
+            store.save(&path)?;
+
+            println!("Snippet '{name}' marked as accessed.");
+        }
+        "--delete-prefix" => {
+            let prefix = name.expect("a prefix is required after --delete-prefix");
+            if prefix.is_empty() {
+                return Err("--delete-prefix needs a non-empty prefix".into());
+            }
+
+            let path = storage_path();
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            let deleted = store.delete_prefix(&prefix);
+
+            if dry_run {
+                println!("[dry-run] Would delete {deleted} snippet(s).");
+                return Ok(());
+            }
+            if deleted > 0 {
+                store.save(&path)?;
+            }
+
+            println!("{deleted} snippet(s) deleted.");
+        }
+        "--delete-matching" => {
+            let pattern = name.expect("a glob pattern is required after --delete-matching");
+            let yes = match args.next().as_deref() {
+                Some("--yes" | "-y") => true,
+                Some(other) => return Err(format!("unknown option '{other}'").into()),
+                None => false,
+            };
+
+            let path = storage_path();
+            let store = if dry_run {
+                load_preview(&path)?
+            } else {
+                load_shared(&path)?
+            };
+            let mut matched: Vec<String> = store
+                .names()
+                .filter(|name| glob::matches(&pattern, name))
+                .map(String::from)
+                .collect();
+            matched.sort_unstable();
+            if matched.is_empty() {
+                println!("No snippets match '{pattern}'.");
+                return Ok(());
+            }
+
+            for name in &matched {
+                println!("{name}");
+            }
+            if dry_run {
+                println!("[dry-run] Would delete {} snippet(s).", matched.len());
+                return Ok(());
+            }
+            let prompt = format!("Delete {} snippet(s)?", matched.len());
+            if !yes && !confirm(io::stdin().lock(), io::stdout(), &prompt)? {
+                println!("Aborted.");
+                return Ok(());
+            }
+
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            let names: Vec<&str> = matched.iter().map(String::as_str).collect();
+            let deleted = store.delete_many(&names);
+            store.save(&path)?;
+
+            println!("{deleted} snippet(s) deleted.");
+        }
+        "--prune-older-than" => {
+            let age = name.expect("a duration is required after --prune-older-than");
+            let age = date::parse_duration(&age)?;
+            let prefix = match args.next().as_deref() {
+                Some("--prefix") => args.next().expect("a prefix is required after --prefix"),
+                Some(other) => return Err(format!("unknown option '{other}'").into()),
+                None => String::new(),
+            };
+            let cutoff = unix_now().saturating_sub(age);
+
+            let path = storage_path();
+            let store = if dry_run {
+                load_preview(&path)?
+            } else {
+                load_shared(&path)?
+            };
+            // Legacy entries have no creation time, so they are never pruned.
+            let mut stale: Vec<String> = store
+                .names()
+                .filter(|name| {
+                    name.starts_with(&prefix)
+                        && store.created_at(name).is_some_and(|at| at < cutoff)
+                })
+                .map(String::from)
+                .collect();
+            stale.sort_unstable();
+            if stale.is_empty() {
+                println!("No snippets are older than the cutoff.");
+                return Ok(());
+            }
+
+            for name in &stale {
+                println!("{name}");
             }
+            if dry_run {
+                println!("[dry-run] Would prune {} snippet(s).", stale.len());
+                return Ok(());
+            }
+
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            let names: Vec<&str> = stale.iter().map(String::as_str).collect();
+            let pruned = store.delete_many(&names);
+            store.save(&path)?;
+
+            println!("{pruned} snippet(s) pruned.");
         }
+        "--clear" => {
+            let yes = match name.as_deref() {
+                Some("--yes" | "-y") => true,
+                Some(other) => return Err(format!("unknown option '{other}'").into()),
+                None => false,
+            };
+
+            let path = storage_path();
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+
+            if !yes {
+                println!(
+                    "{} snippet(s) would be deleted; rerun with --yes to clear the store.",
+                    store.len()
+                );
+                return Ok(());
+            }
+            if dry_run {
+                println!("[dry-run] Would delete {} snippet(s).", store.len());
+                return Ok(());
+            }
+            let cleared = store.clear();
+            store.save(&path)?;
+
+            println!("{cleared} snippet(s) deleted.");
+        }
+        "--placeholders" => {
+            let name = name.expect("snippet name is required after --placeholders");
+            let store = load_shared(&storage_path())?;
+
+            let code = store.checked_get(&name)?;
+            for placeholder in template::list_placeholders(code) {
+                println!("{placeholder}");
+            }
+        }
+        "--diff" => {
+            let old_name = name.expect("snippet name is required after --diff");
+            let new_name = args.next();
+            let store = load_shared(&storage_path())?;
+
+            let old = store.checked_get(&old_name)?;
+            let (new_label, new) = match new_name {
+                Some(new_name) => {
+                    let code = store.checked_get(&new_name)?.to_string();
+                    (new_name, code)
+                }
+                None => {
+                    let code = read_code(io::stdin(), max_snippet_bytes()?)?;
+                    ("stdin".to_string(), code)
+                }
+            };
+
+            print!("{}", diff::unified(&old_name, old, &new_label, &new));
+        }
+        "--exists" => {
+            let name = name.expect("snippet name is required after --exists");
+            let store = load_shared(&storage_path())?;
+
+            process::exit(if store.exists(&name) { 0 } else { 1 });
+        }
+        "--auto-name" => {
+            let code = read_code(io::stdin(), max_snippet_bytes()?)?;
+            let base = name_from_code(&code).ok_or("cannot derive a name from empty code")?;
+
+            let path = storage_path();
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            let name = store.unique_name(&base);
+
+            if dry_run {
+                println!("[dry-run] Would save snippet '{name}'.");
+                return Ok(());
+            }
+
+            store.insert(name.clone(), code);
+            store.save(&path)?;
+
+            println!("Snippet '{name}' saved.");
+        }
+        "--batch" => {
+            let max_bytes = max_snippet_bytes()?;
+            // JSON escapes such as \u0000 can make a body up to six times longer.
+            let line_limit = max_bytes
+                .saturating_mul(6)
+                .saturating_add(BATCH_LINE_OVERHEAD);
+            let mut input = io::stdin().lock();
+            let mut snippets = Vec::new();
+            let mut failed = 0;
+            let mut number = 0;
+
+            while let Some(line) = read_bounded_line(&mut input, line_limit)? {
+                number += 1;
+                let line = match line.and_then(utf8) {
+                    Ok(line) => line,
+                    Err(err) => {
+                        eprintln!("Line {number}: {err}");
+                        failed += 1;
+                        continue;
+                    }
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Snippet>(&line) {
+                    Ok(snippet) if snippet.code.len() as u64 > max_bytes => {
+                        let err = too_large(snippet.code.len() as u64, max_bytes);
+                        eprintln!("Line {number}: {err}");
+                        failed += 1;
+                    }
+                    Ok(snippet) => snippets.push(snippet),
+                    Err(err) => {
+                        eprintln!("Line {number}: {err}");
+                        failed += 1;
+                    }
+                }
+            }
+
+            let saved = snippets.len();
+            if dry_run {
+                println!("[dry-run] Would save {saved} snippet(s), {failed} failed.");
+                return Ok(());
+            }
+
+            let path = storage_path();
+            let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+            for snippet in snippets {
+                store.insert_snippet(snippet);
+            }
+            store.save(&path)?;
+
+            println!("{saved} snippet(s) saved, {failed} failed.");
+        }
+        "--watch-stdin" => {
+            let mut prefix = String::from("capture-");
+            let mut options = name.into_iter().chain(args.by_ref());
+            while let Some(arg) = options.next() {
+                match arg.as_str() {
+                    "--name-prefix" => {
+                        prefix = options
+                            .next()
+                            .expect("a prefix is required after --name-prefix");
+                    }
+                    other => return Err(format!("unknown option '{other}'").into()),
+                }
+            }
+
+            if read_only_mode() && !dry_run {
+                return Err(read_only_error().into());
+            }
+            let max_bytes = max_snippet_bytes()?;
+            let path = storage_path();
+            let mut input = io::stdin().lock();
+            let mut captured = 0;
+
+            while let Some(line) = read_bounded_line(&mut input, max_bytes)? {
+                let line = match line.and_then(utf8) {
+                    Ok(line) => line,
+                    Err(err) => {
+                        eprintln!("Skipped line: {err}");
+                        continue;
+                    }
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let millis = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_millis())
+                    .unwrap_or_default();
+                let (_lock, mut store) = load_for_writing(&path, dry_run)?;
+                let name = store.unique_name(&format!("{prefix}{millis}"));
+
+                if dry_run {
+                    println!("[dry-run] Would save snippet '{name}'.");
+                } else {
+                    store.insert(name.clone(), line);
+                    store.save(&path)?;
+                    println!("Snippet '{name}' saved.");
+                }
+                captured += 1;
+            }
+
+            println!("{captured} line(s) captured.");
+        }
+        "--verify" => {
+            let store = load_shared(&storage_path())?;
+            let corrupted = store.verify();
+            if corrupted.is_empty() {
+                println!("All {} snippet(s) match their checksums.", store.len());
+            } else {
+                for name in &corrupted {
+                    eprintln!("Snippet '{name}' does not match its checksum.");
+                }
+                return Err(format!("{} corrupted snippet(s) found", corrupted.len()).into());
+            }
+        }
+        "--doctor" => doctor(&storage_path())?,
         _ => {
             eprintln!(
                 "Usage:
-  echo \"code\" | snippets-app --name \"Cool Rust pattern\"
-  snippets-app --read \"Cool Rust pattern\"
-  snippets-app --delete \"Cool Rust pattern\""
+  echo \"code\" | snippets-app --name \"Cool Rust pattern\" [--lang rust] [--meta author=me] [--append]
+  echo \"more code\" | snippets-app --append \"Scratch\" [--file more.rs]
+  echo \"code\" | snippets-app --auto-name
+  snippets-app --name \"Cool Rust pattern\" --file pattern.rs
+  snippets-app --name \"Cooler Rust pattern\" --copy-from \"Cool Rust pattern\" [--force]
+  snippets-app --read \"Cool Rust pattern\" [--read \"Another\" | \"Another\" ...]
+  snippets-app --read \"Deploy\" --set host=prod --set user=root [--allow-unfilled]
+  snippets-app --placeholders \"Deploy\"
+  snippets-app --read \"Deploy\" --expand-env [--strict-env]
+  snippets-app --read \"Cool Rust pattern\" --clipboard
+  snippets-app --read \"Cool Rust pattern\" --numbers
+  snippets-app --last [--numbers] [--clipboard] [--json]
+  snippets-app --name \"logo\" --binary --file logo.png
+  snippets-app --diff \"Cool Rust pattern\" \"Cooler Rust pattern\"
+  echo \"code\" | snippets-app --diff \"Cool Rust pattern\"
+  snippets-app --list [--lang rust] [--filter-meta author=me] [--offset 20] [--limit 10] [--table]
+  snippets-app --list --since 2024-03-01 [--until 2024-03-08T12:00:00Z]
+  snippets-app --export backup.json
+  snippets-app --import backup.json [--import-mode skip|overwrite|rename]
+  snippets-app --count
+  snippets-app --stats
+  snippets-app --search \"reqwest\"
+  snippets-app --pick [\"rust\"]
+  snippets-app --open \"Cool Rust pattern\"
+  snippets-app --edit \"Cool Rust pattern\"
+  snippets-app --rename \"Cool Rust pattern\" \"Cool Rust idiom\"
+  snippets-app --touch \"Cool Rust pattern\"
+  snippets-app --history \"Cool Rust pattern\"
+  snippets-app --restore \"Cool Rust pattern\" [--version 2]
+  snippets-app --delete \"Cool Rust pattern\" [--yes]
+  snippets-app --delete-prefix \"tmp-\"
+  snippets-app --delete-matching \"tmp-*\" [--yes]
+  snippets-app --prune-older-than 30d [--prefix tmp-]
+  snippets-app --clear --yes
+  snippets-app --exists \"Cool Rust pattern\"
+  cat snippets.ndjson | snippets-app --batch
+  some-command | snippets-app --watch-stdin [--name-prefix \"capture-\"]
+  snippets-app --verify
+  snippets-app --doctor
+
+--edit opens a snippet (or a new empty one) in $VISUAL, $EDITOR or vi, and
+saves it only if the editor exits successfully with changed content.
+
+Mutating commands (--name, --append, --auto-name, --edit, --rename, --touch,
+--restore, --delete, --delete-prefix, --delete-matching, --prune-older-than,
+--clear, --import, --batch, --watch-stdin) accept --dry-run to report what they
+would do without touching the store. A dry run takes no write lock and also
+works under SNIPPETS_APP_READ_ONLY=1.
+
+--prune-older-than deletes snippets created longer ago than a duration made of
+numbers with s, m, h or d units (e.g. 30d, 1d12h). --prefix limits it to names
+starting with a prefix. Snippets without a creation time are never pruned.
+
+--list prints each name with when it was created (RFC 3339, UTC), or
+\"unknown\" for snippets saved before creation times were kept. --since and
+--until take YYYY-MM-DD (midnight UTC) or RFC 3339 and keep snippets created
+at or after --since and before --until; snippets without a creation time are
+left out when either is given.
+
+--read and --list accept --json to print snippets as JSON instead: one object
+for a single --read name, otherwise an array. Objects include created_at and
+updated_at (unix seconds) when the snippet has them.
+
+Saved snippets are limited to SNIPPETS_APP_MAX_BYTES bytes (default 1 MiB).
+
+--name --binary stores any bytes, base64-encoded and marked as binary.
+--read writes a binary snippet's raw bytes back out; it has to be read on its
+own, without --set, --expand-env, --numbers or --clipboard. With --json, its
+code stays base64 and the object carries \"binary\": true.
+
+snippets.json is pretty-printed; set SNIPPETS_APP_JSON_COMPACT=1 to write it
+compactly instead. Either form is read back transparently.
+
+--delete asks for confirmation when stdin is a terminal; pass --yes to skip it.
+
+Set SNIPPETS_APP_HISTORY_DEPTH=N to keep the last N bodies of each snippet
+when it is overwritten. --history lists them newest first, and
+--restore --version K brings one back (K defaults to 1) and always keeps
+the body it replaces in history.
+
+Set SNIPPETS_APP_READ_ONLY=1 to browse a shared store without risk: any
+command that would write it fails instead.
+
+A corrupted snippets.json is an error. Set SNIPPETS_APP_RECOVER=1 to move it
+aside as snippets.json.corrupt-<timestamp> and start from an empty store.
+
+Exit codes:
+  0  success
+  1  error
+  3  a requested snippet was not found (--read, --open, --placeholders,
+     --copy-from, --diff, --rename, --touch, --history, --restore,
+     --delete); --read still prints the snippets it did find"
             );
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
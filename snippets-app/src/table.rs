@@ -0,0 +1,104 @@
+use crate::{date, Snippet};
+
+const GAP: &str = "  ";
+const MIN_NAME_WIDTH: usize = 8;
+
+pub fn format_table(snippets: &[Snippet], width: usize) -> String {
+    let rows: Vec<(&str, &str, String, String)> = snippets
+        .iter()
+        .map(|snippet| {
+            let language = snippet.language.as_deref().unwrap_or("-");
+            // The day is enough to tell snippets apart at a glance.
+            let created = snippet.created_at.map_or("unknown".to_string(), |at| {
+                date::format(at)[..10].to_string()
+            });
+            (
+                snippet.name.as_str(),
+                language,
+                snippet.code.lines().count().to_string(),
+                created,
+            )
+        })
+        .collect();
+
+    let widest = |header: &str, cells: &mut dyn Iterator<Item = usize>| {
+        cells.fold(header.chars().count(), usize::max)
+    };
+    let language_width = widest(
+        "LANGUAGE",
+        &mut rows
+            .iter()
+            .map(|(_, language, _, _)| language.chars().count()),
+    );
+    let lines_width = widest(
+        "LINES",
+        &mut rows.iter().map(|(_, _, lines, _)| lines.len()),
+    );
+    let created_width = widest(
+        "CREATED",
+        &mut rows.iter().map(|(_, _, _, created)| created.len()),
+    );
+    let room = width
+        .saturating_sub(language_width + lines_width + created_width + 3 * GAP.len())
+        .max(MIN_NAME_WIDTH);
+    let name_width = widest(
+        "NAME",
+        &mut rows.iter().map(|(name, _, _, _)| name.chars().count()),
+    )
+    .min(room);
+
+    let mut out = format!(
+        "{:<name_width$}{GAP}{:<language_width$}{GAP}{:>lines_width$}{GAP}{}\n",
+        "NAME", "LANGUAGE", "LINES", "CREATED"
+    );
+    for (name, language, lines, created) in &rows {
+        out.push_str(&format!(
+            "{:<name_width$}{GAP}{language:<language_width$}{GAP}{lines:>lines_width$}{GAP}{created}\n",
+            truncate(name, name_width)
+        ));
+    }
+    out
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(width - 1).collect();
+    short.push('…');
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::snippet;
+
+    #[test]
+    fn aligns_columns_for_mixed_length_names() {
+        let mut snippets = [
+            snippet("a", "one", Some("rust")),
+            snippet("a much longer name", "one\ntwo\nthree", None),
+        ];
+        snippets[0].created_at = Some(1_709_251_200);
+
+        assert_eq!(
+            format_table(&snippets, 80),
+            "NAME                LANGUAGE  LINES  CREATED\n\
+             a                   rust          1  2024-03-01\n\
+             a much longer name  -             3  unknown\n"
+        );
+    }
+
+    #[test]
+    fn truncates_names_that_do_not_fit() {
+        let snippets = [snippet("deploy the staging cluster", "x", Some("sh"))];
+
+        let table = format_table(&snippets, 39);
+        assert_eq!(
+            table,
+            "NAME           LANGUAGE  LINES  CREATED\n\
+             deploy the s…  sh            1  unknown\n"
+        );
+    }
+}
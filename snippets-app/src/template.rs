@@ -0,0 +1,119 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+#[derive(Debug, PartialEq)]
+pub struct UnfilledPlaceholders(pub Vec<String>);
+
+impl fmt::Display for UnfilledPlaceholders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unfilled placeholders: {}", self.0.join(", "))
+    }
+}
+
+impl Error for UnfilledPlaceholders {}
+
+pub fn list_placeholders(code: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = code;
+
+    while let Some((_, name, after)) = next_placeholder(rest) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+        rest = after;
+    }
+    names
+}
+
+pub fn render(
+    code: &str,
+    values: &HashMap<String, String>,
+    allow_unfilled: bool,
+) -> Result<String, UnfilledPlaceholders> {
+    let mut out = String::with_capacity(code.len());
+    let mut missing = Vec::new();
+    let mut rest = code;
+
+    while let Some((start, name, after)) = next_placeholder(rest) {
+        out.push_str(&rest[..start]);
+        match values.get(name) {
+            Some(value) => out.push_str(value),
+            None => {
+                out.push_str(&rest[start..rest.len() - after.len()]);
+                if !missing.iter().any(|m| m == name) {
+                    missing.push(name.to_string());
+                }
+            }
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+
+    if missing.is_empty() || allow_unfilled {
+        Ok(out)
+    } else {
+        Err(UnfilledPlaceholders(missing))
+    }
+}
+
+fn next_placeholder(text: &str) -> Option<(usize, &str, &str)> {
+    let mut from = 0;
+    loop {
+        let start = from + text[from..].find("{{")?;
+        let end = start + 2 + text[start + 2..].find("}}")?;
+        let name = text[start + 2..end].trim();
+
+        if !name.is_empty() {
+            return Some((start, name, &text[end + 2..]));
+        }
+        from = end + 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn lists_unique_placeholders_in_order() {
+        let code = "curl {{ host }}/{{path}}?q={{host}}";
+        assert_eq!(list_placeholders(code), ["host", "path"]);
+    }
+
+    #[test]
+    fn renders_all_values() {
+        let code = "ssh {{user}}@{{ host }}";
+        let out = render(code, &values(&[("user", "root"), ("host", "db")]), false);
+        assert_eq!(out.unwrap(), "ssh root@db");
+    }
+
+    #[test]
+    fn unfilled_placeholder_is_an_error() {
+        let err = render("ssh {{user}}@{{host}}", &values(&[("user", "root")]), false);
+        assert_eq!(err, Err(UnfilledPlaceholders(vec!["host".into()])));
+    }
+
+    #[test]
+    fn unfilled_placeholder_is_kept_when_allowed() {
+        let out = render(
+            "ssh {{user}}@{{ host }}",
+            &values(&[("user", "root")]),
+            true,
+        );
+        assert_eq!(out.unwrap(), "ssh root@{{ host }}");
+    }
+
+    #[test]
+    fn empty_braces_are_not_placeholders() {
+        assert_eq!(
+            render("{{}} {{x}}", &values(&[("x", "1")]), false).unwrap(),
+            "{{}} 1"
+        );
+    }
+}
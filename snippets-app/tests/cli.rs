@@ -0,0 +1,650 @@
+use snippets_app::Snippet;
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("snippets-app-cli-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str], stdin: impl AsRef<[u8]>, envs: &[(&str, &str)]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_snippets-app"))
+        .args(args)
+        .envs(envs.iter().copied())
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The binary may reject its input and exit before reading all of it.
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_ref());
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn reading_a_missing_snippet_exits_with_not_found_code() {
+    let dir = temp_dir("read-missing");
+    let output = run(&dir, &["--read", "missing"], "", &[]);
+
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Snippet 'missing' not found."));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn deleting_a_missing_snippet_exits_with_not_found_code() {
+    let dir = temp_dir("delete-missing");
+    let output = run(&dir, &["--delete", "missing"], "", &[]);
+
+    assert_eq!(output.status.code(), Some(3));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn read_only_mode_refuses_to_delete() {
+    let dir = temp_dir("read-only");
+    let store = r#"{"snippets": {"keep": "echo keep"}}"#;
+    fs::write(dir.join("snippets.json"), store).unwrap();
+
+    let output = run(
+        &dir,
+        &["--delete", "keep"],
+        "",
+        &[("SNIPPETS_APP_READ_ONLY", "1")],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        fs::read_to_string(dir.join("snippets.json")).unwrap(),
+        store
+    );
+    assert!(!dir.join("snippets.json.lock").exists());
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn read_only_mode_still_reads() {
+    let dir = temp_dir("read-only-reads");
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {"keep": "echo keep"}}"#,
+    )
+    .unwrap();
+    let envs = [("SNIPPETS_APP_READ_ONLY", "1"), ("VISUAL", "false")];
+
+    assert_eq!(
+        stdout(&run(&dir, &["--read", "keep"], "", &envs)),
+        "echo keep\n"
+    );
+    assert_eq!(
+        stdout(&run(&dir, &["--list"], "", &envs)),
+        "keep\tunknown\n"
+    );
+
+    let edit = run(&dir, &["--edit", "keep"], "", &envs);
+    assert_eq!(edit.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&edit.stderr).contains("read-only"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn clear_without_yes_reports_and_keeps_the_store() {
+    let dir = temp_dir("clear-guarded");
+    let store = r#"{"snippets": {"a": "1", "b": "2"}}"#;
+    fs::write(dir.join("snippets.json"), store).unwrap();
+
+    let output = stdout(&run(&dir, &["--clear"], "", &[]));
+
+    assert!(output.contains("2 snippet(s) would be deleted"));
+    assert_eq!(
+        fs::read_to_string(dir.join("snippets.json")).unwrap(),
+        store
+    );
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn clear_with_yes_empties_the_store() {
+    let dir = temp_dir("clear-confirmed");
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {"a": "1", "b": "2"}}"#,
+    )
+    .unwrap();
+
+    let output = stdout(&run(&dir, &["--clear", "--yes"], "", &[]));
+
+    assert!(output.contains("2 snippet(s) deleted."));
+    assert_eq!(stdout(&run(&dir, &["--count"], "", &[])).trim(), "0");
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn json_output_parses_back_into_snippets() {
+    let dir = temp_dir("json");
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {
+            "a": {"code": "fn a() {}", "language": "rust", "created_at": 100, "updated_at": 200},
+            "b": "echo b"
+        }}"#,
+    )
+    .unwrap();
+
+    let read = stdout(&run(&dir, &["--read", "a", "--json"], "", &[]));
+    let read: Snippet = serde_json::from_str(&read).unwrap();
+    assert_eq!(read.name, "a");
+    assert_eq!(read.code, "fn a() {}");
+    assert_eq!(read.language.as_deref(), Some("rust"));
+    assert_eq!((read.created_at, read.updated_at), (Some(100), Some(200)));
+
+    let listed = stdout(&run(&dir, &["--json", "--list"], "", &[]));
+    let listed: Vec<Snippet> = serde_json::from_str(&listed).unwrap();
+    let names: Vec<&str> = listed.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["a", "b"]);
+    assert_eq!(listed[0].created_at, Some(100));
+    assert_eq!(listed[1].language, None);
+    assert_eq!(listed[1].created_at, None);
+
+    let legacy = stdout(&run(&dir, &["--read", "b", "--json"], "", &[]));
+    assert!(!legacy.contains("created_at"), "{legacy}");
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn file_and_copy_from_are_mutually_exclusive() {
+    let dir = temp_dir("file-exclusive");
+    let output = run(
+        &dir,
+        &["--name", "x", "--file", "x.rs", "--copy-from", "y"],
+        "",
+        &[],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("use only one of --file and --copy-from"));
+    assert!(!dir.join("snippets.json").exists());
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn name_reads_the_body_from_a_file() {
+    let dir = temp_dir("file");
+    fs::write(dir.join("hello.rs"), "fn main() {}\n").unwrap();
+
+    stdout(&run(
+        &dir,
+        &["--name", "hello", "--file", "hello.rs"],
+        "",
+        &[],
+    ));
+    let read = stdout(&run(&dir, &["--read", "hello"], "", &[]));
+    assert_eq!(read, "fn main() {}\n\n");
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn batch_overwrite_keeps_history() {
+    let dir = temp_dir("batch-history");
+    let envs = [("SNIPPETS_APP_HISTORY_DEPTH", "5")];
+    let cli = |args: &[&str], stdin: &str| stdout(&run(&dir, args, stdin, &envs));
+
+    cli(&["--name", "a", "--lang", "rust"], "v1");
+    cli(&["--name", "a"], "v2");
+    cli(&["--batch"], "{\"name\": \"a\", \"code\": \"v3\"}\n");
+
+    assert_eq!(cli(&["--read", "a"], ""), "v3\n");
+    let history = cli(&["--history", "a"], "");
+    let lines: Vec<&str> = history.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("Created at ") && !lines[0].contains("unknown"));
+    assert!(lines[1].starts_with(" 1) ") && lines[1].ends_with("  v2"));
+    assert!(lines[2].starts_with(" 2) ") && lines[2].ends_with("  v1"));
+    let listed = cli(&["--list", "--lang", "rust"], "");
+    let (name, created) = listed.trim_end().split_once('\t').unwrap();
+    assert_eq!(name, "a");
+    assert!(created.ends_with('Z') && created.len() == 20, "{created}");
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn corrupted_snippet_is_not_copied_or_appended_to() {
+    let dir = temp_dir("tampered");
+    let cli = |args: &[&str], stdin: &str| run(&dir, args, stdin, &[]);
+
+    assert!(cli(&["--name", "a"], "echo original").status.success());
+    let store = dir.join("snippets.json");
+    let tampered = fs::read_to_string(&store)
+        .unwrap()
+        .replace("echo original", "echo tampered");
+    fs::write(&store, tampered).unwrap();
+
+    assert_eq!(cli(&["--verify"], "").status.code(), Some(1));
+    assert_eq!(
+        cli(&["--name", "b", "--copy-from", "a"], "").status.code(),
+        Some(1)
+    );
+    assert_eq!(cli(&["--append", "a"], "more").status.code(), Some(1));
+    assert_eq!(cli(&["--read", "b"], "").status.code(), Some(3));
+    let verify = cli(&["--verify"], "");
+    assert_eq!(verify.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&verify.stderr).contains("'a'"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn delete_matching_rejects_unknown_options() {
+    let dir = temp_dir("delete-matching-unknown");
+    let output = run(&dir, &["--delete-matching", "tmp-*", "--yse"], "", &[]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown option '--yse'"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn diff_against_stdin_prints_hunks_and_respects_the_size_limit() {
+    let dir = temp_dir("diff-stdin");
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {"a": "one\ntwo"}}"#,
+    )
+    .unwrap();
+    let diff = |input: &str, max_bytes: &str| {
+        run(
+            &dir,
+            &["--diff", "a"],
+            input,
+            &[("SNIPPETS_APP_MAX_BYTES", max_bytes)],
+        )
+    };
+
+    assert_eq!(
+        stdout(&diff("one\n2\n", "100")),
+        "--- a\n+++ stdin\n@@ -1,2 +1,2 @@\n one\n-two\n+2\n"
+    );
+
+    let output = diff("one\n2\n", "3");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("more than 3 bytes"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn open_without_a_viewer_prints_and_removes_the_temp_file() {
+    let dir = temp_dir("open-fallback");
+    let tmp = dir.join("tmp");
+    fs::create_dir_all(&tmp).unwrap();
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {"a/b": "echo hi"}}"#,
+    )
+    .unwrap();
+
+    let no_viewers = dir.join("no-viewers");
+    let envs = [
+        ("PATH", no_viewers.to_str().unwrap()),
+        ("TMPDIR", tmp.to_str().unwrap()),
+    ];
+    let output = run(&dir, &["--open", "a/b"], "", &envs);
+
+    assert_eq!(stdout(&output), "echo hi\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("could not open a viewer"));
+    assert_eq!(fs::read_dir(&tmp).unwrap().count(), 0);
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn metadata_is_saved_shown_and_filtered() {
+    let dir = temp_dir("metadata");
+    let cli = |args: &[&str], stdin: &str| stdout(&run(&dir, args, stdin, &[]));
+
+    cli(
+        &[
+            "--name",
+            "a",
+            "--meta",
+            "author=me",
+            "--meta",
+            "source_url=x",
+        ],
+        "a",
+    );
+    cli(&["--name", "b", "--meta", "author=you"], "b");
+    cli(&["--name", "c"], "c");
+
+    let listed = cli(&["--list", "--filter-meta", "author=me"], "");
+    assert!(listed.starts_with("a\t") && listed.lines().count() == 1);
+    assert_eq!(cli(&["--list", "--filter-meta", "author=nobody"], ""), "");
+    let read = cli(&["--read", "a", "--json"], "");
+    let snippet: Snippet = serde_json::from_str(&read).unwrap();
+    assert_eq!(snippet.metadata["author"], "me");
+    assert_eq!(snippet.metadata["source_url"], "x");
+    assert!(!cli(&["--read", "c", "--json"], "").contains("metadata"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn oversized_input_is_rejected_without_reading_it_all() {
+    let dir = temp_dir("oversized");
+    let envs = [("SNIPPETS_APP_MAX_BYTES", "10")];
+    let long = "x".repeat(64 * 1024);
+
+    let saved = run(&dir, &["--name", "big"], &long, &envs);
+    assert_eq!(saved.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&saved.stderr).contains("more than 10 bytes"));
+
+    let watched = run(&dir, &["--watch-stdin"], format!("{long}\nshort\n"), &envs);
+    assert!(stdout(&watched).ends_with("1 line(s) captured.\n"));
+    assert!(String::from_utf8_lossy(&watched.stderr).contains("Skipped line"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn bad_history_depth_only_breaks_writes() {
+    let dir = temp_dir("bad-depth");
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {"a": "echo a"}}"#,
+    )
+    .unwrap();
+    let envs = [("SNIPPETS_APP_HISTORY_DEPTH", "lots")];
+
+    assert_eq!(stdout(&run(&dir, &["--read", "a"], "", &envs)), "echo a\n");
+    assert_eq!(stdout(&run(&dir, &["--count"], "", &envs)), "1\n");
+    let save = run(&dir, &["--name", "a"], "echo b", &envs);
+    assert_eq!(save.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&save.stderr).contains("SNIPPETS_APP_HISTORY_DEPTH"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn batch_counts_invalid_utf8_as_a_failed_line() {
+    let dir = temp_dir("batch-utf8");
+    let input = [
+        &br#"{"name": "a", "code": "1"}"#[..],
+        b"\n\xff\xfe\n",
+        br#"{"name": "b", "code": "2"}"#,
+        b"\n",
+    ]
+    .concat();
+
+    let output = run(&dir, &["--batch"], input, &[]);
+    assert_eq!(stdout(&output), "2 snippet(s) saved, 1 failed.\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Line 2:"));
+    assert_eq!(stdout(&run(&dir, &["--count"], "", &[])), "2\n");
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn last_reads_the_most_recently_created_snippet() {
+    let dir = temp_dir("last");
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {
+            "legacy": "echo legacy",
+            "older": {"code": "older", "created_at": 10, "updated_at": 99},
+            "newer": {"code": "newer", "created_at": 20}
+        }}"#,
+    )
+    .unwrap();
+    let cli = |args: &[&str]| run(&dir, args, "", &[]);
+
+    assert_eq!(stdout(&cli(&["--last"])), "newer\n");
+    assert_eq!(stdout(&cli(&["--last", "--numbers"])), "1 | newer\n");
+    let snippet: Snippet = serde_json::from_str(&stdout(&cli(&["--last", "--json"]))).unwrap();
+    assert_eq!(
+        (snippet.name.as_str(), snippet.created_at),
+        ("newer", Some(20))
+    );
+
+    let extra = cli(&["--last", "older"]);
+    assert_eq!(extra.status.code(), Some(1));
+
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {"legacy": "x"}}"#,
+    )
+    .unwrap();
+    let none = cli(&["--last"]);
+    assert_eq!(none.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&none.stderr).contains("no snippets"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn list_filters_by_creation_date() {
+    let dir = temp_dir("since-until");
+    // 2024-03-01T00:00:00Z is 1709251200.
+    fs::write(
+        dir.join("snippets.json"),
+        r#"{"snippets": {
+            "legacy": "echo",
+            "before": {"code": "x", "created_at": 1709251199},
+            "midnight": {"code": "x", "created_at": 1709251200},
+            "later": {"code": "x", "created_at": 1709337600}
+        }}"#,
+    )
+    .unwrap();
+    let names = |args: &[&str]| -> Vec<String> {
+        let mut full = vec!["--list"];
+        full.extend(args);
+        stdout(&run(&dir, &full, "", &[]))
+            .lines()
+            .map(|line| line.split('\t').next().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(names(&["--since", "2024-03-01"]), ["later", "midnight"]);
+    assert_eq!(names(&["--until", "2024-03-01"]), ["before"]);
+    assert_eq!(
+        names(&["--since", "2024-03-01", "--until", "2024-03-02T00:00:00Z"]),
+        ["midnight"]
+    );
+    assert_eq!(
+        names(&["--since", "2024-03-01T01:00:00+01:00"]),
+        ["later", "midnight"]
+    );
+    assert_eq!(names(&[]), ["before", "later", "legacy", "midnight"]);
+
+    let bad = run(&dir, &["--list", "--since", "2024-02-30"], "", &[]);
+    assert_eq!(bad.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&bad.stderr).contains("invalid date '2024-02-30'"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn prune_removes_only_old_snippets() {
+    let dir = temp_dir("prune");
+    let store = dir.join("snippets.json");
+    fs::write(
+        &store,
+        r#"{"snippets": {
+            "legacy": "echo",
+            "old": {"code": "x", "created_at": 100},
+            "tmp-old": {"code": "x", "created_at": 100},
+            "fresh": {"code": "x", "created_at": 4000000000}
+        }}"#,
+    )
+    .unwrap();
+    let cli = |args: &[&str]| stdout(&run(&dir, args, "", &[]));
+    let names = || -> Vec<String> {
+        cli(&["--list"])
+            .lines()
+            .map(|line| line.split('\t').next().unwrap().to_string())
+            .collect()
+    };
+
+    let before = fs::read_to_string(&store).unwrap();
+    assert_eq!(
+        cli(&["--prune-older-than", "30d", "--dry-run"]),
+        "old\ntmp-old\n[dry-run] Would prune 2 snippet(s).\n"
+    );
+    assert_eq!(fs::read_to_string(&store).unwrap(), before);
+
+    assert_eq!(
+        cli(&["--prune-older-than", "1d12h", "--prefix", "tmp-"]),
+        "tmp-old\n1 snippet(s) pruned.\n"
+    );
+    assert_eq!(names(), ["fresh", "legacy", "old"]);
+
+    assert_eq!(
+        cli(&["--prune-older-than", "30d"]),
+        "old\n1 snippet(s) pruned.\n"
+    );
+    assert_eq!(names(), ["fresh", "legacy"]);
+
+    let bad = run(&dir, &["--prune-older-than", "1w"], "", &[]);
+    assert_eq!(bad.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&bad.stderr).contains("invalid duration '1w'"));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn binary_snippets_keep_their_exact_bytes() {
+    let dir = temp_dir("binary");
+    let cli = |args: &[&str], stdin: &[u8]| run(&dir, args, stdin, &[]);
+    let bytes = b"\x89PNG\r\n\x1a\n\xff\x00";
+
+    let saved = cli(&["--name", "logo", "--binary"], bytes);
+    assert!(saved.status.success(), "{saved:?}");
+    let read = cli(&["--read", "logo"], b"");
+    assert!(read.status.success(), "{read:?}");
+    assert_eq!(read.stdout, bytes);
+
+    let json = stdout(&cli(&["--read", "logo", "--json"], b""));
+    let snippet: Snippet = serde_json::from_str(&json).unwrap();
+    assert!(snippet.binary);
+    assert_eq!(snippet.code, "iVBORw0KGgr/AA==");
+
+    stdout(&cli(&["--name", "copy", "--copy-from", "logo"], b""));
+    assert_eq!(cli(&["--read", "copy"], b"").stdout, bytes);
+
+    for args in [
+        &["--read", "logo", "--numbers"][..],
+        &["--read", "logo", "copy"],
+        &["--append", "logo", "--binary"],
+        &["--append", "logo"],
+    ] {
+        let output = cli(args, b"more");
+        assert_eq!(output.status.code(), Some(1), "{args:?}");
+    }
+    assert_eq!(cli(&["--read", "logo"], b"").stdout, bytes);
+
+    let text = cli(&["--name", "text"], bytes);
+    assert_eq!(text.status.code(), Some(1));
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn doctor_probes_the_directory_without_leaving_files() {
+    let dir = temp_dir("doctor");
+    let doctor = || stdout(&run(&dir, &["--doctor"], "", &[]));
+
+    let fresh = doctor();
+    assert!(fresh.contains("Directory writable: yes"), "{fresh}");
+    assert!(fresh.contains("not created yet"), "{fresh}");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+    stdout(&run(&dir, &["--name", "a"], "a", &[]));
+    let saved = doctor();
+    assert!(saved.contains("Directory writable: yes"), "{saved}");
+    assert!(saved.contains("JSON: valid, 1 snippet(s)"), "{saved}");
+    let mut files: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["snippets.json", "snippets.json.lock"]);
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn dry_runs_leave_no_lock_and_work_read_only() {
+    let dir = temp_dir("dry-run");
+    let store = r#"{"snippets": {"a": {"code": "echo a", "created_at": 100}, "tmp-b": "echo b"}}"#;
+    fs::write(dir.join("snippets.json"), store).unwrap();
+    let envs = [("SNIPPETS_APP_READ_ONLY", "1")];
+
+    for (args, stdin) in [
+        (&["--name", "c", "--dry-run"][..], "echo c"),
+        (&["--append", "a", "--dry-run"], "more"),
+        (&["--rename", "a", "z", "--dry-run"], ""),
+        (&["--touch", "a", "--dry-run"], ""),
+        (&["--delete", "a", "--dry-run"], ""),
+        (&["--delete-prefix", "tmp-", "--dry-run"], ""),
+        (&["--delete-matching", "tmp-*", "--dry-run"], ""),
+        (&["--prune-older-than", "1d", "--dry-run"], ""),
+        (&["--clear", "--yes", "--dry-run"], ""),
+        (&["--auto-name", "--dry-run"], "echo auto"),
+        (&["--batch", "--dry-run"], r#"{"name": "d", "code": "d"}"#),
+        (&["--watch-stdin", "--dry-run"], "echo watched\n"),
+    ] {
+        let output = run(&dir, args, stdin, &envs);
+        assert!(output.status.success(), "{args:?}: {output:?}");
+        assert!(
+            String::from_utf8_lossy(&output.stdout).contains("[dry-run]"),
+            "{args:?}"
+        );
+        assert!(!dir.join("snippets.json.lock").exists(), "{args:?}");
+        assert_eq!(
+            fs::read_to_string(dir.join("snippets.json")).unwrap(),
+            store
+        );
+    }
+
+    fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn recovering_a_corrupted_store_warns_on_stderr() {
+    let dir = temp_dir("recover");
+    fs::write(dir.join("snippets.json"), "{ not json").unwrap();
+
+    let output = run(&dir, &["--list"], "", &[("SNIPPETS_APP_RECOVER", "1")]);
+    assert_eq!(stdout(&output), "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.starts_with("warning: snippets.json is corrupted (")
+            && stderr.contains("moved it to snippets.json.corrupt-"),
+        "{stderr}"
+    );
+
+    fs::remove_dir_all(dir).ok();
+}